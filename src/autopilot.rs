@@ -0,0 +1,172 @@
+use bevy::{ecs::query::ReadOnlyWorldQuery, prelude::*};
+use bevy_rapier2d::prelude::*;
+
+use crate::characters::CharacterDefinition;
+use crate::env;
+use crate::flappy::{Flap, Flappy};
+use crate::pause::PauseState;
+use crate::pipes::{GapSensor, PipeGroup, PIPE_WIDTH};
+use crate::replay::ReplayPlayback;
+use crate::training::Genome;
+use crate::AppState;
+use crate::SimulationSet;
+
+/// Flies the first player's bird through the pipe gaps whenever an [`Autopilot`] resource
+/// is inserted, by sending the same [`Flap`]s the player's input does.
+pub struct AutopilotPlugin;
+
+impl Plugin for AutopilotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            reset_autopilot.run_if(resource_exists::<Autopilot>()),
+        )
+        .add_systems(
+            FixedUpdate,
+            steer
+                .in_set(SimulationSet::Input)
+                .run_if(resource_exists::<Autopilot>())
+                .run_if(not(resource_exists::<ReplayPlayback>()))
+                .run_if(in_state(AppState::InGame))
+                .run_if(in_state(PauseState::Running)),
+        );
+    }
+}
+
+/// Flaps whenever the bird would otherwise sink below the next gap.
+///
+/// It doesn't know how high a flap lifts the bird, so it measures each one and aims to
+/// keep the whole arc centred on the gap. Given a trained [`Genome`], it lets that fly instead.
+#[derive(Resource, Debug, Default)]
+pub struct Autopilot {
+    /// Height the last flap gained before the bird started falling again.
+    rise: f32,
+    /// Where the bird last flapped, until it stops rising.
+    flapped_at: Option<f32>,
+    genome: Option<Genome>,
+}
+
+impl Autopilot {
+    pub fn with_genome(genome: Genome) -> Self {
+        Self {
+            genome: Some(genome),
+            ..default()
+        }
+    }
+
+    /// Whether to flap now, given what the bird sees and the height it is at.
+    pub fn decide(&mut self, observation: &Observation, height: f32, delta: f32) -> bool {
+        if let Some(flapped_at) = self.flapped_at {
+            if observation.vertical_speed > 0.0 {
+                return false;
+            }
+            self.rise = height - flapped_at;
+            self.flapped_at = None;
+        }
+        // one step late, since the flap only lands next step
+        let next_height = observation.above_gap + observation.vertical_speed * delta;
+        if next_height + self.rise / 2.0 < 0.0 {
+            self.flapped_at = Some(height);
+            return true;
+        }
+        false
+    }
+}
+
+/// The bird and the next gap it has to fly through, as the bird sees them.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Observation {
+    /// Height of the bird above the middle of the gap.
+    pub above_gap: f32,
+    pub vertical_speed: f32,
+    /// How far ahead the middle of the gap is.
+    pub gap_distance: f32,
+    pub gap_size: f32,
+}
+
+impl Observation {
+    /// What the bird sees given the centre and size of every gap on screen. With no gap
+    /// ahead it aims for the middle of the playfield, at no distance and no size.
+    pub fn new(
+        flappy: &Transform,
+        velocity: &Velocity,
+        character: &CharacterDefinition,
+        gaps: impl Iterator<Item = (Vec2, f32)>,
+    ) -> Self {
+        let bird = flappy.translation.truncate();
+        let (centre, gap_size) = upcoming_gaps(flappy, character, gaps)
+            .first()
+            .copied()
+            .unwrap_or((Vec2::new(bird.x, 0.0), 0.0));
+        Self {
+            above_gap: bird.y - centre.y,
+            vertical_speed: velocity.linvel.y,
+            gap_distance: centre.x - bird.x,
+            gap_size,
+        }
+    }
+}
+
+/// The gaps the bird still has to fly through, nearest first. A gap stays ahead until
+/// the bird is clear of its pipes.
+pub fn upcoming_gaps(
+    flappy: &Transform,
+    character: &CharacterDefinition,
+    gaps: impl Iterator<Item = (Vec2, f32)>,
+) -> Vec<(Vec2, f32)> {
+    let clear_x = flappy.translation.x - PIPE_WIDTH / 2.0 - character.sprite_size / 2.0;
+    let mut upcoming: Vec<_> = gaps.filter(|(centre, _)| centre.x > clear_x).collect();
+    upcoming.sort_by(|(a, _), (b, _)| a.x.total_cmp(&b.x));
+    upcoming
+}
+
+/// Centre and size of each gap, from the pipe pairs holding a [`GapSensor`].
+pub fn gaps<F: ReadOnlyWorldQuery>(
+    q_gap_sensors: &Query<&Parent, With<GapSensor>>,
+    q_pipe_groups: &Query<(&Transform, &PipeGroup), F>,
+) -> Vec<(Vec2, f32)> {
+    q_gap_sensors
+        .iter()
+        .filter_map(|parent| {
+            let (transform, pipe_group) = q_pipe_groups.get(parent.get()).ok()?;
+            Some((transform.translation.truncate(), pipe_group.gap_size))
+        })
+        .collect()
+}
+
+fn reset_autopilot(mut autopilot: ResMut<Autopilot>) {
+    *autopilot = Autopilot {
+        genome: autopilot.genome.take(),
+        ..default()
+    };
+}
+
+fn steer(
+    time: Res<Time>,
+    mut autopilot: ResMut<Autopilot>,
+    mut flaps: EventWriter<Flap>,
+    q_flappy: Query<(&Flappy, &Transform, &Velocity, &CharacterDefinition)>,
+    q_gap_sensors: Query<&Parent, With<GapSensor>>,
+    q_pipe_groups: Query<(&Transform, &PipeGroup)>,
+) {
+    let Some((_, transform, velocity, character)) =
+        q_flappy.iter().find(|(flappy, ..)| flappy.0 == 0)
+    else {
+        return;
+    };
+    let gaps = gaps(&q_gap_sensors, &q_pipe_groups);
+    let flap = if let Some(genome) = &autopilot.genome {
+        genome.flaps(&env::Observation::new(
+            transform,
+            velocity,
+            character,
+            gaps.into_iter(),
+        ))
+    } else {
+        let observation = Observation::new(transform, velocity, character, gaps.into_iter());
+        autopilot.decide(&observation, transform.translation.y, time.delta_seconds())
+    };
+    if flap {
+        flaps.send(Flap(0));
+    }
+}
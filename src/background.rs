@@ -1,18 +1,17 @@
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use rand::Rng;
 use std::time::Duration;
 
-const LAYER_1_SPEED: f32 = -50.0;
-
-const CLOUD_SPEED_MIN: f32 = -500.0;
-const CLOUD_SPEED_MAX: f32 = -50.0;
+use crate::interpolation::Interpolated;
+use crate::pause::PauseState;
+use crate::playfield::Playfield;
+use crate::power_ups::{ScrollSpeed, Scrolls};
+use crate::rng::GameRng;
+use crate::tuning::Tuning;
+use crate::SimulationSet;
 
-const CLOUD_SCALE_MIN: f32 = 0.25;
-const CLOUD_SCALE_MAX: f32 = 2.0;
-
-const CLOUD_DISTANCE_MIN: f32 = 0.0;
-const CLOUD_DISTANCE_MAX: f32 = 1.0;
+const LAYER_1_SPEED: f32 = -50.0;
 
 pub struct BackgroundPlugin;
 
@@ -22,7 +21,12 @@ impl Plugin for BackgroundPlugin {
             Startup,
             (spawn_despawn_area, spawn, setup_cloud_spawn_timer),
         )
-        .add_systems(Update, (detect_and_despawn, cloud_spawner));
+        .add_systems(
+            FixedUpdate,
+            (detect_and_despawn, cloud_spawner)
+                .in_set(SimulationSet::Gameplay)
+                .run_if(in_state(PauseState::Running)),
+        );
     }
 }
 
@@ -42,20 +46,12 @@ struct Cloud;
 #[derive(Component)]
 struct DespawnArea;
 
-fn spawn(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-) {
-    let Ok(window) = window_query.get_single() else {
-        return;
-    };
-
+fn spawn(mut commands: Commands, asset_server: Res<AssetServer>, playfield: Res<Playfield>) {
     commands
         .spawn(SpriteBundle {
             texture: asset_server.load("mountains.png"),
             transform: Transform {
-                translation: Vec3::new(0.0, -(window.width() / 2.0 - 100.0), 0.0),
+                translation: Vec3::new(0.0, -(playfield.width / 2.0 - 100.0), 0.0),
                 ..default()
             },
             sprite: Sprite { ..default() },
@@ -68,6 +64,7 @@ fn spawn(
             Group::from_bits(0b0001).unwrap(),
         ))
         .insert(Velocity::linear(Vec2::new(LAYER_1_SPEED, 0.0)))
+        .insert(Interpolated::default())
         .insert(Background)
         .insert(ActiveEvents::COLLISION_EVENTS)
         .insert(Scenario);
@@ -92,41 +89,43 @@ fn translate_value_from_one_range_to_another(
     (((current_value - current_range_min) * new_range) / current_range) + new_range_min
 }
 
+#[allow(clippy::too_many_arguments)]
 fn cloud_spawner(
     mut commands: Commands,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    playfield: Res<Playfield>,
     time: Res<Time>,
     mut config: ResMut<CloudsSpawnConfig>,
     asset_server: Res<AssetServer>,
+    mut game_rng: ResMut<GameRng>,
+    tuning: Res<Tuning>,
+    scroll_speed: Res<ScrollSpeed>,
 ) {
     config.timer.tick(time.delta());
 
     if config.timer.finished() {
-        let Ok(window) = window_query.get_single() else {
-            return;
-        };
-        let mut rng = rand::thread_rng();
+        let rng = &mut game_rng.clouds;
+        let tuning = &tuning.clouds;
         if rng.gen_bool(0.5) {
-            let initial_height_variation = window.height() - window.height() * 0.1;
+            let initial_height_variation = playfield.height - playfield.height * 0.1;
             let initial_position_y =
                 rng.gen_range(-initial_height_variation..initial_height_variation);
-            let initial_position_x = window.width() * 2.0;
+            let initial_position_x = playfield.width * 2.0;
 
-            let speed = rng.gen_range(CLOUD_SPEED_MIN..CLOUD_SPEED_MAX);
+            let speed = rng.gen_range(tuning.speed_min..tuning.speed_max);
             let scale = translate_value_from_one_range_to_another(
                 speed,
-                CLOUD_SPEED_MIN,
-                CLOUD_SPEED_MAX,
-                CLOUD_SCALE_MIN,
-                CLOUD_SCALE_MAX,
+                tuning.speed_min,
+                tuning.speed_max,
+                tuning.scale_min,
+                tuning.scale_max,
             );
-            let distance = CLOUD_DISTANCE_MAX
+            let distance = tuning.distance_max
                 - translate_value_from_one_range_to_another(
                     speed,
-                    CLOUD_SPEED_MIN,
-                    CLOUD_SPEED_MAX,
-                    CLOUD_DISTANCE_MIN,
-                    CLOUD_DISTANCE_MAX,
+                    tuning.speed_min,
+                    tuning.speed_max,
+                    tuning.distance_min,
+                    tuning.distance_max,
                 );
 
             let image_path = match rng.gen_range(1..9) {
@@ -162,22 +161,21 @@ fn cloud_spawner(
                     Group::from_bits(0b0001).unwrap(),
                     Group::from_bits(0b0001).unwrap(),
                 ))
-                .insert(Velocity::linear(Vec2::new(speed, 0.0)))
+                .insert(Velocity::linear(Vec2::new(speed * scroll_speed.0, 0.0)))
+                .insert(Interpolated::default())
                 .insert(Cloud)
+                .insert(Scrolls)
                 .insert(ActiveEvents::COLLISION_EVENTS)
                 .insert(Scenario);
         }
     }
 }
 
-fn spawn_despawn_area(mut commands: Commands, window_query: Query<&Window, With<PrimaryWindow>>) {
-    let Ok(window) = window_query.get_single() else {
-        return;
-    };
-    let x_position = -(4.0 * window.width());
-    // let x_position = -window.width() - 200.0;
+fn spawn_despawn_area(mut commands: Commands, playfield: Res<Playfield>) {
+    let x_position = -(4.0 * playfield.width);
+    // let x_position = -playfield.width - 200.0;
     let width = 50.0;
-    let height = window.height() * 2.0;
+    let height = playfield.height * 2.0;
 
     commands
         .spawn(SpriteBundle {
@@ -0,0 +1,100 @@
+use std::path::PathBuf;
+
+use bevy_flappy::training::{self, Training};
+
+const DEFAULT_POPULATION: usize = 100;
+const DEFAULT_GENERATIONS: u32 = 50;
+const DEFAULT_OUTPUT: &str = "genome.ron";
+// a bird still flying after this long is good enough to stop and score
+const MAX_GENERATION_SECS: f64 = 120.0;
+
+/// Evolves birds headlessly and saves the best genome for `--genome` to fly.
+fn main() {
+    let options = Options::parse();
+    let mut app = training::app(Training::new(
+        options.population,
+        options.seed,
+        MAX_GENERATION_SECS,
+    ));
+
+    let mut generation = 0;
+    let mut best_score = None;
+    while generation < options.generations {
+        app.update();
+        let training = app.world.resource::<Training>();
+        if training.generation == generation {
+            continue;
+        }
+        generation = training.generation;
+        let Some((genome, fitness)) = training.best() else {
+            continue;
+        };
+        println!(
+            "generation {generation}: best score {} over {} ticks",
+            fitness.score, fitness.ticks
+        );
+        if best_score != Some(fitness) {
+            best_score = Some(fitness);
+            if let Err(error) = genome.write(&options.output) {
+                eprintln!(
+                    "error: could not save genome {}: {error}",
+                    options.output.display()
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+    println!("best genome saved to {}", options.output.display());
+}
+
+struct Options {
+    population: usize,
+    generations: u32,
+    seed: u64,
+    output: PathBuf,
+}
+
+impl Options {
+    /// Parses the process arguments, exiting with a usage error if they are invalid.
+    fn parse() -> Self {
+        match Self::try_parse(std::env::args().skip(1)) {
+            Ok(options) => options,
+            Err(message) => {
+                eprintln!("error: {message}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    fn try_parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self {
+            population: DEFAULT_POPULATION,
+            generations: DEFAULT_GENERATIONS,
+            seed: 0,
+            output: DEFAULT_OUTPUT.into(),
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("`{arg}` expects a value"))
+            };
+            match arg.as_str() {
+                "--population" => options.population = number(&value()?)?,
+                "--generations" => options.generations = number(&value()?)?,
+                "--seed" => options.seed = number(&value()?)?,
+                "--out" => options.output = value()?.into(),
+                other => return Err(format!("unknown argument `{other}`")),
+            }
+        }
+        if options.population == 0 {
+            return Err("`--population` must be at least 1".into());
+        }
+        Ok(options)
+    }
+}
+
+fn number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("`{value}` is not a valid number"))
+}
@@ -0,0 +1,78 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
+
+use crate::flappy::Lethal;
+use crate::playfield::Playfield;
+use crate::tuning::Tuning;
+use crate::AppState;
+
+const BOUND_THICKNESS: f32 = 100.0;
+
+/// Closes the playfield with a lethal floor and a ceiling the bird can't leave through.
+pub struct BoundsPlugin;
+
+impl Plugin for BoundsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Ceiling>()
+            .add_systems(
+                OnEnter(AppState::InGame),
+                (select_ceiling, spawn_bounds).chain(),
+            )
+            .add_systems(OnExit(AppState::InGame), despawn_bounds);
+    }
+}
+
+/// What happens when the bird flies into the top of the playfield.
+///
+/// The current behaviour is a resource so a game mode can swap in its own.
+#[derive(Resource, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Ceiling {
+    /// The bird bumps against it and keeps flying.
+    #[default]
+    Clamp,
+    /// Touching it ends the run, like hitting a pipe.
+    Lethal,
+}
+
+#[derive(Component)]
+struct Bound;
+
+fn select_ceiling(tuning: Res<Tuning>, mut ceiling: ResMut<Ceiling>) {
+    *ceiling = tuning.bounds.ceiling_behaviour;
+}
+
+fn spawn_bounds(
+    mut commands: Commands,
+    playfield: Res<Playfield>,
+    tuning: Res<Tuning>,
+    ceiling: Res<Ceiling>,
+) {
+    // the camera shows twice the playfield size, so its edges are at +/- width and height
+    let width = playfield.width * 2.0;
+    let floor_y = -playfield.height + tuning.bounds.floor - BOUND_THICKNESS / 2.0;
+    let ceiling_y = playfield.height + tuning.bounds.ceiling + BOUND_THICKNESS / 2.0;
+
+    for (y, lethal) in [(floor_y, true), (ceiling_y, *ceiling == Ceiling::Lethal)] {
+        let mut bound = commands.spawn((
+            TransformBundle::from_transform(Transform::from_xyz(0.0, y, 0.0)),
+            Collider::cuboid(width, BOUND_THICKNESS / 2.0),
+            // a group of their own that only the bird collides with
+            CollisionGroups::new(
+                Group::from_bits(0b1000).unwrap(),
+                Group::from_bits(0b0100).unwrap(),
+            ),
+            ActiveEvents::COLLISION_EVENTS,
+            Bound,
+        ));
+        if lethal {
+            bound.insert(Lethal);
+        }
+    }
+}
+
+fn despawn_bounds(mut commands: Commands, query: Query<Entity, With<Bound>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
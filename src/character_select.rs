@@ -0,0 +1,225 @@
+use bevy::prelude::*;
+
+use crate::characters::{CharacterDefinition, SelectedCharacter};
+use crate::tuning::Tuning;
+use crate::widgets::spawn_button;
+use crate::AppState;
+
+const PREVIEW_SIZE: f32 = 120.0;
+
+/// The screen between the main menu and a run where the player picks a bird.
+pub struct CharacterSelectPlugin;
+
+impl Plugin for CharacterSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::CharacterSelect), spawn_character_select)
+            .add_systems(
+                Update,
+                (
+                    character_select_buttons,
+                    character_select_keys,
+                    show_selected_character,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::CharacterSelect)),
+            )
+            .add_systems(OnExit(AppState::CharacterSelect), despawn_character_select);
+    }
+}
+
+#[derive(Component)]
+struct CharacterSelectMenu;
+
+#[derive(Component)]
+struct CharacterPreview;
+
+#[derive(Component)]
+struct CharacterDetails;
+
+#[derive(Component, Clone)]
+enum CharacterSelectAction {
+    Pick(String),
+    Play,
+    Back,
+}
+
+fn spawn_character_select(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    tuning: Res<Tuning>,
+    mut selected: ResMut<SelectedCharacter>,
+) {
+    // settle an empty or stale name on the character the run would actually use
+    let character = tuning.character(&selected.0);
+    if selected.0 != character.name {
+        selected.0 = character.name.clone();
+    }
+
+    let font = asset_server.load("fonts/Monocraft.otf");
+    let text_margin = Style {
+        margin: UiRect::all(Val::Px(5.0)),
+        ..default()
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(CharacterSelectMenu)
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    "Choose your bird",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 50.0,
+                        color: Color::hsl(153.0, 0.67, 0.28),
+                    },
+                )
+                .with_style(text_margin.clone()),
+            );
+            parent
+                .spawn(ImageBundle {
+                    style: Style {
+                        width: Val::Px(PREVIEW_SIZE),
+                        height: Val::Px(PREVIEW_SIZE),
+                        margin: UiRect::all(Val::Px(10.0)),
+                        ..default()
+                    },
+                    image: UiImage::new(asset_server.load(&character.sprite)),
+                    background_color: character.color().into(),
+                    ..default()
+                })
+                .insert(CharacterPreview);
+            parent
+                .spawn(TextBundle::from_section(
+                    describe(character),
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 20.0,
+                        color: Color::rgb(0.086, 0.086, 0.086),
+                    },
+                ))
+                .insert(CharacterDetails);
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        margin: UiRect::top(Val::Px(20.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for character in &tuning.characters {
+                        spawn_button(
+                            parent,
+                            &font,
+                            &character.name,
+                            CharacterSelectAction::Pick(character.name.clone()),
+                        );
+                    }
+                });
+            parent.spawn(NodeBundle::default()).with_children(|parent| {
+                spawn_button(parent, &font, "Play", CharacterSelectAction::Play);
+                spawn_button(parent, &font, "Back", CharacterSelectAction::Back);
+            });
+            parent.spawn(
+                TextBundle::from_section(
+                    "Left and right to choose, space to play",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 20.0,
+                        color: Color::rgb(0.086, 0.086, 0.086),
+                    },
+                )
+                .with_style(text_margin),
+            );
+        });
+}
+
+fn character_select_buttons(
+    mut app_state: ResMut<NextState<AppState>>,
+    mut selected: ResMut<SelectedCharacter>,
+    q_buttons: Query<(&Interaction, &CharacterSelectAction), Changed<Interaction>>,
+) {
+    for (interaction, action) in q_buttons.iter() {
+        if *interaction == Interaction::Pressed {
+            match action {
+                CharacterSelectAction::Pick(name) => selected.0 = name.clone(),
+                CharacterSelectAction::Play => app_state.set(AppState::InGame),
+                CharacterSelectAction::Back => app_state.set(AppState::GameStart),
+            }
+        }
+    }
+}
+
+fn character_select_keys(
+    mut app_state: ResMut<NextState<AppState>>,
+    mut selected: ResMut<SelectedCharacter>,
+    key_buttons: Res<Input<KeyCode>>,
+    tuning: Res<Tuning>,
+) {
+    let count = tuning.characters.len();
+    let index = tuning
+        .characters
+        .iter()
+        .position(|character| character.name == selected.0)
+        .unwrap_or(0);
+    if key_buttons.just_pressed(KeyCode::Left) {
+        selected.0 = tuning.characters[(index + count - 1) % count].name.clone();
+    }
+    if key_buttons.just_pressed(KeyCode::Right) {
+        selected.0 = tuning.characters[(index + 1) % count].name.clone();
+    }
+    if key_buttons.any_just_pressed([KeyCode::Space, KeyCode::Return]) {
+        app_state.set(AppState::InGame);
+    }
+    if key_buttons.just_pressed(KeyCode::Escape) {
+        app_state.set(AppState::GameStart);
+    }
+}
+
+fn show_selected_character(
+    asset_server: Res<AssetServer>,
+    tuning: Res<Tuning>,
+    selected: Res<SelectedCharacter>,
+    mut q_preview: Query<(&mut UiImage, &mut BackgroundColor), With<CharacterPreview>>,
+    mut q_details: Query<&mut Text, With<CharacterDetails>>,
+) {
+    if !selected.is_changed() && !tuning.is_changed() {
+        return;
+    }
+    let character = tuning.character(&selected.0);
+    for (mut image, mut tint) in q_preview.iter_mut() {
+        *image = UiImage::new(asset_server.load(&character.sprite));
+        *tint = character.color().into();
+    }
+    for mut text in q_details.iter_mut() {
+        text.sections[0].value = describe(character);
+    }
+}
+
+fn describe(character: &CharacterDefinition) -> String {
+    format!(
+        "{}  Gravity: {}  Flap: {}  Max fall: {}",
+        character.name, character.gravity_scale, character.flap_impulse, character.max_fall_speed,
+    )
+}
+
+fn despawn_character_select(
+    mut commands: Commands,
+    query: Query<Entity, With<CharacterSelectMenu>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
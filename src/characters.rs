@@ -0,0 +1,141 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
+
+/// Which of [`crate::tuning::Tuning::characters`] the next run is flown with, by name.
+/// An empty or unknown name picks the first one.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct SelectedCharacter(pub String);
+
+/// How a bird looks and flies. Also a component on the bird it describes.
+#[derive(Component, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CharacterDefinition {
+    pub name: String,
+    /// Image under `assets/`.
+    pub sprite: String,
+    /// Red, green and blue multiplied into the sprite.
+    pub tint: [f32; 3],
+    pub sprite_size: f32,
+    pub collider: CharacterCollider,
+    pub gravity_scale: f32,
+    pub flap_impulse: f32,
+    /// Fastest the bird falls, in pixels per second.
+    pub max_fall_speed: f32,
+    pub rotation: CharacterRotation,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub enum CharacterCollider {
+    Box { width: f32, height: f32 },
+    Circle { diameter: f32 },
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub enum CharacterRotation {
+    /// Noses up `flap_angle` degrees on each flap, then turns towards a dive a share of
+    /// the way every step: `rising_rate` while going up, `falling_rate` while coming down.
+    Tilt {
+        flap_angle: f32,
+        rising_rate: f32,
+        falling_rate: f32,
+    },
+    /// Stays level.
+    Upright,
+}
+
+impl CharacterDefinition {
+    pub fn color(&self) -> Color {
+        let [red, green, blue] = self.tint;
+        Color::rgb(red, green, blue)
+    }
+
+    /// The bird's collider at `scale` times its normal size.
+    pub fn scaled_collider(&self, scale: f32) -> Collider {
+        match self.collider {
+            CharacterCollider::Box { width, height } => {
+                Collider::cuboid(width * scale / 2.0, height * scale / 2.0)
+            }
+            CharacterCollider::Circle { diameter } => Collider::ball(diameter * scale / 2.0),
+        }
+    }
+
+    /// Describes what makes the character unusable, if anything.
+    pub fn problem(&self) -> Option<String> {
+        let name = &self.name;
+        let collider_ok = match self.collider {
+            CharacterCollider::Box { width, height } => width > 0.0 && height > 0.0,
+            CharacterCollider::Circle { diameter } => diameter > 0.0,
+        };
+        if name.is_empty() {
+            Some("characters need a name".to_string())
+        } else if self.sprite_size <= 0.0 || !collider_ok {
+            Some(format!(
+                "character {name} needs a positive sprite and collider size"
+            ))
+        } else if self.gravity_scale <= 0.0 || self.flap_impulse <= 0.0 {
+            Some(format!(
+                "character {name} needs positive gravity and flap impulse"
+            ))
+        } else if self.max_fall_speed <= 0.0 {
+            Some(format!("character {name} needs a positive max fall speed"))
+        } else {
+            None
+        }
+    }
+}
+
+/// The characters the game ships with.
+pub fn built_in() -> Vec<CharacterDefinition> {
+    vec![
+        CharacterDefinition {
+            name: "Bevy".to_string(),
+            sprite: "bevy.png".to_string(),
+            tint: [0.0, 0.0, 0.0],
+            sprite_size: 100.0,
+            collider: CharacterCollider::Box {
+                width: 100.0,
+                height: 100.0,
+            },
+            gravity_scale: 25.0,
+            flap_impulse: 800.0,
+            max_fall_speed: 1500.0,
+            rotation: CharacterRotation::Tilt {
+                flap_angle: 60.0,
+                rising_rate: 0.02,
+                falling_rate: 0.04,
+            },
+        },
+        CharacterDefinition {
+            name: "Feather".to_string(),
+            sprite: "bevy.png".to_string(),
+            tint: [0.2, 0.45, 0.9],
+            sprite_size: 80.0,
+            collider: CharacterCollider::Circle { diameter: 76.0 },
+            gravity_scale: 15.0,
+            flap_impulse: 500.0,
+            max_fall_speed: 700.0,
+            rotation: CharacterRotation::Tilt {
+                flap_angle: 30.0,
+                rising_rate: 0.01,
+                falling_rate: 0.02,
+            },
+        },
+        CharacterDefinition {
+            name: "Brick".to_string(),
+            sprite: "bevy.png".to_string(),
+            tint: [0.7, 0.2, 0.15],
+            sprite_size: 110.0,
+            collider: CharacterCollider::Box {
+                width: 110.0,
+                height: 110.0,
+            },
+            gravity_scale: 40.0,
+            flap_impulse: 1300.0,
+            max_fall_speed: 2500.0,
+            rotation: CharacterRotation::Upright,
+        },
+    ]
+}
@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+
+use crate::game_modes::GameMode;
+use crate::players::{PlayerCount, MAX_PLAYERS};
+
+/// Options read from the command line.
+#[derive(Debug, Default)]
+pub struct Args {
+    pub headless: bool,
+    /// Let the autopilot fly the bird.
+    pub autopilot: bool,
+    /// Genome file for the autopilot to fly by, saved by the trainer.
+    pub genome: Option<PathBuf>,
+    pub seed: Option<u64>,
+    pub character: Option<String>,
+    pub mode: Option<GameMode>,
+    pub players: Option<PlayerCount>,
+    /// Level file under `assets/`.
+    pub level: Option<String>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+}
+
+impl Args {
+    /// Parses the process arguments, exiting with a usage error if they are invalid.
+    pub fn parse() -> Self {
+        match Self::try_parse(std::env::args().skip(1)) {
+            Ok(args) => args,
+            Err(message) => {
+                eprintln!("error: {message}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    fn try_parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => parsed.headless = true,
+                "--autopilot" => parsed.autopilot = true,
+                "--seed" => {
+                    let value = value_of(&mut args, "--seed")?;
+                    let seed = value
+                        .parse()
+                        .map_err(|_| format!("`{value}` is not a valid seed"))?;
+                    parsed.seed = Some(seed);
+                }
+                "--character" => parsed.character = Some(value_of(&mut args, "--character")?),
+                "--mode" => {
+                    let value = value_of(&mut args, "--mode")?;
+                    let mode = GameMode::from_name(&value)
+                        .ok_or_else(|| format!("`{value}` is not a game mode"))?;
+                    parsed.mode = Some(mode);
+                }
+                "--genome" => parsed.genome = Some(value_of(&mut args, "--genome")?.into()),
+                "--players" => {
+                    let value = value_of(&mut args, "--players")?;
+                    let players = value
+                        .parse()
+                        .ok()
+                        .filter(|players| (1..=MAX_PLAYERS).contains(players))
+                        .ok_or_else(|| format!("`{value}` is not 1 to {MAX_PLAYERS} players"))?;
+                    parsed.players = Some(PlayerCount(players));
+                }
+                "--level" => parsed.level = Some(value_of(&mut args, "--level")?),
+                "--record" => parsed.record = Some(value_of(&mut args, "--record")?.into()),
+                "--replay" => parsed.replay = Some(value_of(&mut args, "--replay")?.into()),
+                other => return Err(format!("unknown argument `{other}`")),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+fn value_of(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("`{flag}` expects a value"))
+}
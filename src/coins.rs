@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+use std::collections::HashSet;
+
+use crate::flappy::Flappy;
+use crate::levels::LevelRun;
+use crate::pause::PauseState;
+use crate::pipes::{self, PipeGroup};
+use crate::rng::GameRng;
+use crate::tuning::Tuning;
+use crate::AppState;
+use crate::SimulationSet;
+
+const COIN_SIZE: f32 = 40.0;
+const COIN_COLOR: Color = Color::rgb(0.96, 0.76, 0.1);
+// room between a risky coin and the pipe it hugs
+const COIN_EDGE_MARGIN: f32 = 10.0;
+
+/// Lays trails of coins through pipe gaps and counts the ones the bird picks up.
+pub struct CoinsPlugin;
+
+impl Plugin for CoinsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Coins>()
+            .add_systems(OnEnter(AppState::InGame), reset_coins)
+            .add_systems(
+                FixedUpdate,
+                (
+                    spawn_coins
+                        .after(pipes::spawn_pipe)
+                        .run_if(not(resource_exists::<LevelRun>())),
+                    collect_coins,
+                )
+                    .in_set(SimulationSet::Gameplay)
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+/// Coins picked up in the current run, kept apart from [`crate::Score`].
+#[derive(Resource, Default)]
+pub struct Coins(pub u32);
+
+#[derive(Component)]
+pub struct Coin;
+
+fn reset_coins(mut coins: ResMut<Coins>) {
+    coins.0 = 0;
+}
+
+/// Gives some new pipe pairs a row of coins through the gap, either down the middle or
+/// hugging one of the pipes.
+fn spawn_coins(
+    mut commands: Commands,
+    q_new_pipe_group: Query<(Entity, &PipeGroup), Added<PipeGroup>>,
+    mut game_rng: ResMut<GameRng>,
+    tuning: Res<Tuning>,
+) {
+    let tuning = &tuning.coins;
+    for (entity, pipe_group) in q_new_pipe_group.iter() {
+        let rng = &mut game_rng.coins;
+        if !rng.gen_bool(tuning.chance) {
+            continue;
+        }
+
+        let edge = pipe_group.gap_size / 2.0 - COIN_SIZE / 2.0 - COIN_EDGE_MARGIN;
+        let y = if rng.gen_bool(tuning.risky_chance) {
+            if rng.gen_bool(0.5) {
+                edge
+            } else {
+                -edge
+            }
+        } else {
+            0.0
+        };
+
+        commands.entity(entity).with_children(|parent| {
+            let first_x = -(tuning.count as f32 - 1.0) * tuning.spacing / 2.0;
+            for index in 0..tuning.count {
+                parent.spawn(coin_bundle(Vec3::new(
+                    first_x + index as f32 * tuning.spacing,
+                    y,
+                    0.5,
+                )));
+            }
+        });
+    }
+}
+
+/// A coin at `translation`, for spawning as part of something that moves it.
+pub fn coin_bundle(translation: Vec3) -> impl Bundle {
+    (
+        SpriteBundle {
+            transform: Transform::from_translation(translation),
+            sprite: Sprite {
+                color: COIN_COLOR,
+                custom_size: Some(Vec2::splat(COIN_SIZE)),
+                ..default()
+            },
+            ..default()
+        },
+        Collider::ball(COIN_SIZE / 2.0),
+        // a group of their own that only the bird collides with
+        CollisionGroups::new(
+            Group::from_bits(0b10000).unwrap(),
+            Group::from_bits(0b0100).unwrap(),
+        ),
+        Sensor,
+        ActiveEvents::COLLISION_EVENTS,
+        Coin,
+    )
+}
+
+fn collect_coins(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    q_flappy: Query<Entity, With<Flappy>>,
+    q_coin: Query<Entity, With<Coin>>,
+    mut coins: ResMut<Coins>,
+) {
+    // two birds can reach a coin in the same step, before its despawn applies
+    let mut collected = HashSet::new();
+    for collision_event in collision_events.read() {
+        let CollisionEvent::Started(entity1, entity2, _) = collision_event else {
+            continue;
+        };
+        for (flappy, coin) in [(*entity1, *entity2), (*entity2, *entity1)] {
+            if q_flappy.contains(flappy) && q_coin.contains(coin) && collected.insert(coin) {
+                commands.entity(coin).despawn_recursive();
+                coins.0 += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::tuning::Tuning;
+use crate::AppState;
+
+/// Picks the [`DifficultyCurve`] each run follows.
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DifficultyCurve>()
+            .add_systems(OnEnter(AppState::InGame), select_curve);
+    }
+}
+
+/// How quickly a run goes from the easiest [`DifficultyLevel`] to the hardest.
+///
+/// A curve maps run progress, the number of pipes passed, to an intensity between 0 and 1.
+/// The current curve is a resource so a game mode can swap in its own.
+#[derive(Resource, Deserialize, Debug, Clone, PartialEq)]
+pub enum DifficultyCurve {
+    /// Grows evenly until it is at its hardest after `full_at` pipes.
+    Linear { full_at: f32 },
+    /// Holds each level for `every` pipes, reaching the hardest after `steps` steps.
+    Stepped { every: u32, steps: u32 },
+    /// Ramps up quickly early on and flattens out, at its hardest after `full_at` pipes.
+    Logarithmic { full_at: f32 },
+    /// `(progress, intensity)` points, interpolated linearly between them.
+    Table(Vec<(f32, f32)>),
+}
+
+impl Default for DifficultyCurve {
+    fn default() -> Self {
+        Self::Linear { full_at: 20.0 }
+    }
+}
+
+impl DifficultyCurve {
+    /// Intensity at `progress`, clamped to `0.0..=1.0`.
+    pub fn intensity(&self, progress: f32) -> f32 {
+        let progress = progress.max(0.0);
+        let intensity = match self {
+            Self::Linear { full_at } => progress / full_at,
+            Self::Stepped { every, steps } => (progress / *every as f32).floor() / *steps as f32,
+            Self::Logarithmic { full_at } => progress.ln_1p() / full_at.ln_1p(),
+            Self::Table(points) => {
+                let after = points.partition_point(|(at, _)| *at <= progress);
+                match (after.checked_sub(1).map(|i| points[i]), points.get(after)) {
+                    (Some((from_at, from)), Some(&(to_at, to))) => {
+                        from + (to - from) * (progress - from_at) / (to_at - from_at)
+                    }
+                    (Some((_, last)), None) => last,
+                    (None, Some(&(_, first))) => first,
+                    (None, None) => 0.0,
+                }
+            }
+        };
+        intensity.clamp(0.0, 1.0)
+    }
+
+    /// Describes what makes the curve unusable, if anything.
+    pub fn problem(&self) -> Option<&'static str> {
+        match self {
+            Self::Linear { full_at } | Self::Logarithmic { full_at } if *full_at <= 0.0 => {
+                Some("full_at must be positive")
+            }
+            Self::Stepped { every, steps } if *every == 0 || *steps == 0 => {
+                Some("every and steps must be positive")
+            }
+            Self::Table(points) if points.is_empty() => Some("table must have at least one point"),
+            Self::Table(points) if points.windows(2).any(|pair| pair[0].0 >= pair[1].0) => {
+                Some("table progress must be strictly increasing")
+            }
+            Self::Table(points)
+                if points
+                    .iter()
+                    .any(|(_, intensity)| !(0.0..=1.0).contains(intensity)) =>
+            {
+                Some("table intensities must be between 0 and 1")
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Pipe settings at one point of a run.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct DifficultyLevel {
+    /// Horizontal pipe velocity, negative as pipes move to the left.
+    pub speed: f32,
+    /// Seconds between pipe spawns.
+    pub spawn_interval: f32,
+    pub gap_size: f32,
+    /// Share of the playfield height the gap centre may land anywhere in.
+    pub vertical_variance: f32,
+}
+
+impl DifficultyLevel {
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let lerp = |from: f32, to: f32| from + (to - from) * t;
+        Self {
+            speed: lerp(self.speed, other.speed),
+            spawn_interval: lerp(self.spawn_interval, other.spawn_interval),
+            gap_size: lerp(self.gap_size, other.gap_size),
+            vertical_variance: lerp(self.vertical_variance, other.vertical_variance),
+        }
+    }
+}
+
+pub fn select_curve(tuning: Res<Tuning>, mut curve: ResMut<DifficultyCurve>) {
+    *curve = tuning.difficulty.curve.clone();
+}
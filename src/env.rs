@@ -0,0 +1,166 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::autopilot;
+use crate::characters::CharacterDefinition;
+use crate::flappy::{Flap, Flappy};
+use crate::headless;
+use crate::pipes::{GapSensor, PipeGroup};
+use crate::playfield::Playfield;
+use crate::rng::RngSeed;
+use crate::AppState;
+use crate::Score;
+use crate::SIMULATION_HZ;
+
+/// Reward for each point scored, which passing a gap earns.
+const SCORE_REWARD: f32 = 1.0;
+/// Reward for every second the bird stays alive.
+const SURVIVAL_REWARD_PER_SEC: f32 = 0.1;
+// state changes take effect on the frame after they are requested
+const RESET_FRAMES: usize = 10;
+
+/// The game as a reinforcement learning environment, on the headless app without a window.
+///
+/// Each [`Env::step`] advances the simulation by exactly one fixed tick.
+pub struct Env {
+    app: App,
+    score: u32,
+    done: bool,
+}
+
+/// What the agent does on a step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Wait,
+    Flap,
+}
+
+/// The bird's state and the next two gaps, relative to the bird.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Observation {
+    /// Height of the bird above the middle of the playfield.
+    pub height: f32,
+    pub vertical_speed: f32,
+    /// Nearest first. A gap that hasn't spawned yet is all zeros.
+    pub gaps: [GapObservation; 2],
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GapObservation {
+    /// How far ahead the middle of the gap is.
+    pub distance: f32,
+    /// Height of the middle of the gap above the bird.
+    pub above: f32,
+    pub size: f32,
+}
+
+impl Env {
+    pub fn new() -> Self {
+        Self {
+            app: headless::stepped_app(Playfield::default()),
+            score: 0,
+            done: true,
+        }
+    }
+
+    /// Starts a new episode whose pipes are generated from `seed`.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.app.insert_resource(RngSeed(Some(seed)));
+        let restart = if self.state() == AppState::InGame {
+            AppState::Restarting
+        } else {
+            AppState::InGame
+        };
+        self.app
+            .world
+            .resource_mut::<NextState<AppState>>()
+            .set(restart);
+        for _ in 0..RESET_FRAMES {
+            self.app.update();
+            if self.state() == AppState::InGame {
+                break;
+            }
+        }
+        self.score = 0;
+        self.done = self.state() != AppState::InGame;
+        self.observe()
+    }
+
+    /// Applies `action` and advances one tick. Returns what the bird sees afterwards, the
+    /// reward earned in the tick and whether the episode is over.
+    pub fn step(&mut self, action: Action) -> (Observation, f32, bool) {
+        if self.done {
+            return (self.observe(), 0.0, true);
+        }
+        if action == Action::Flap {
+            self.app.world.send_event(Flap(0));
+        }
+        self.app.update();
+
+        let score = self.app.world.resource::<Score>().0;
+        let mut reward = score.saturating_sub(self.score) as f32 * SCORE_REWARD;
+        self.score = score;
+        // the run ending only shows in the state on the next frame
+        self.done = self.state() != AppState::InGame
+            || self.app.world.resource::<NextState<AppState>>().0.is_some();
+        if !self.done {
+            reward += SURVIVAL_REWARD_PER_SEC / SIMULATION_HZ as f32;
+        }
+        (self.observe(), reward, self.done)
+    }
+
+    fn state(&self) -> AppState {
+        *self.app.world.resource::<State<AppState>>().get()
+    }
+
+    fn observe(&mut self) -> Observation {
+        let world = &mut self.app.world;
+        let gaps: Vec<(Vec2, f32)> = world
+            .query_filtered::<&Parent, With<GapSensor>>()
+            .iter(world)
+            .filter_map(|parent| {
+                let pipe_group = world.get::<PipeGroup>(parent.get())?;
+                let transform = world.get::<Transform>(parent.get())?;
+                Some((transform.translation.truncate(), pipe_group.gap_size))
+            })
+            .collect();
+        let mut q_flappy =
+            world.query_filtered::<(&Transform, &Velocity, &CharacterDefinition), With<Flappy>>();
+        let Ok((transform, velocity, character)) = q_flappy.get_single(world) else {
+            return Observation::default();
+        };
+        Observation::new(transform, velocity, character, gaps.into_iter())
+    }
+}
+
+impl Observation {
+    /// What a bird sees given the centre and size of every gap on screen.
+    pub fn new(
+        bird: &Transform,
+        velocity: &Velocity,
+        character: &CharacterDefinition,
+        gaps: impl Iterator<Item = (Vec2, f32)>,
+    ) -> Self {
+        let position = bird.translation.truncate();
+        let mut observation = Self {
+            height: position.y,
+            vertical_speed: velocity.linvel.y,
+            gaps: default(),
+        };
+        let upcoming = autopilot::upcoming_gaps(bird, character, gaps);
+        for (gap, (centre, size)) in observation.gaps.iter_mut().zip(upcoming) {
+            *gap = GapObservation {
+                distance: centre.x - position.x,
+                above: centre.y - position.y,
+                size,
+            };
+        }
+        observation
+    }
+}
+
+impl Default for Env {
+    fn default() -> Self {
+        Self::new()
+    }
+}
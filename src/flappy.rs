@@ -2,74 +2,241 @@ use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use std::f32::consts::PI;
 
+use crate::characters::{CharacterDefinition, CharacterRotation, SelectedCharacter};
+use crate::interpolation::Interpolated;
+use crate::pause::PauseState;
 use crate::pipes::GapSensor;
-use crate::pipes::PipeBottom;
-use crate::pipes::PipeTop;
+use crate::players::{self, PlayerCount, Scoreboard, PLAYER_CONTROLS, PLAYER_TINTS};
+use crate::power_ups::{ActivePowerUps, PowerUpKind, ScoreMultiplier};
+use crate::replay::ReplayPlayback;
+use crate::training::Training;
+use crate::tuning::Tuning;
 use crate::AppState;
 use crate::Score;
+use crate::SimulationSet;
+
+// how long the bird can pass through pipes after its shield absorbs a hit
+const SHIELD_GRACE_SECS: f32 = 1.0;
+const BLINK_SECS: f32 = 0.1;
+
+const FLAPPY_GROUP: u32 = 0b0100;
+// pipes, the playfield bounds, coins and power-up pickups, but not other birds
+const FLAPPY_FILTER: u32 = 0b111010;
+// what an invulnerable bird still touches: everything but pipes
+const INVULNERABLE_FLAPPY_FILTER: u32 = 0b111000;
 
 pub struct FlappyPlugin;
 
 impl Plugin for FlappyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(AppState::InGame), spawn_flappy)
+        app.add_event::<Flap>()
+            .add_event::<Crashed>()
+            .init_resource::<PendingFlaps>()
+            .add_systems(
+                OnEnter(AppState::InGame),
+                spawn_flappy.run_if(not(resource_exists::<Training>())),
+            )
             .add_systems(
                 Update,
+                in_game_control
+                    .run_if(not(resource_exists::<ReplayPlayback>()))
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_systems(
+                FixedUpdate,
+                send_pending_flaps
+                    .in_set(SimulationSet::Input)
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_systems(
+                FixedUpdate,
                 (
-                    in_game_control,
+                    flap,
                     animate_flappy,
+                    limit_fall_speed,
                     detect_flappy_gap_sensor_collision,
-                    detect_flappy_pipes_collision,
+                    detect_flappy_lethal_collision,
+                    tick_invulnerability,
                 )
-                    .run_if(in_state(AppState::InGame)),
+                    .in_set(SimulationSet::Gameplay)
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_systems(
+                Update,
+                apply_character_tuning.run_if(resource_changed::<Tuning>()),
             )
-            .add_systems(OnExit(AppState::InGame), despawn_flappy);
+            .add_systems(
+                OnExit(AppState::InGame),
+                (despawn_flappy, forget_pending_flaps),
+            );
     }
 }
 
+/// The bird a player flies, numbered from 0 by player.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Flappy(pub usize);
+
+/// Any bird flown through the pipes, whether by the player or by something else.
 #[derive(Component)]
-struct Flappy;
+pub struct Bird;
+
+/// Ends the run when the bird touches it.
+#[derive(Component)]
+pub struct Lethal;
+
+/// Lets the bird fly through pipes until the timer runs out, blinking meanwhile.
+#[derive(Component)]
+pub struct Invulnerable {
+    timer: Timer,
+    blink: Timer,
+}
 
-fn spawn_flappy(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let sprite_size = 100.0;
+impl Invulnerable {
+    /// Makes the bird invulnerable for `secs` when inserted.
+    pub fn bundle(secs: f32) -> (Self, CollisionGroups) {
+        (
+            Self {
+                timer: Timer::from_seconds(secs, TimerMode::Once),
+                blink: Timer::from_seconds(BLINK_SECS, TimerMode::Repeating),
+            },
+            collision_groups(INVULNERABLE_FLAPPY_FILTER),
+        )
+    }
+}
+
+/// The bird hit something lethal and had nothing to absorb it.
+#[derive(Event)]
+pub struct Crashed(pub Entity);
+
+/// Makes the given player's bird flap. Sent for player input, and by anything else that
+/// drives a bird.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Flap(pub usize);
+
+/// Players whose flap input arrived since the last simulation step. Frames can outpace the
+/// simulation, so input is held here until the next step turns it into [`Flap`]s.
+#[derive(Resource, Default)]
+pub struct PendingFlaps(pub Vec<usize>);
+
+fn spawn_flappy(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    tuning: Res<Tuning>,
+    selected: Res<SelectedCharacter>,
+    players: Res<PlayerCount>,
+) {
+    let character = tuning.character(&selected.0);
+    for (player, tint) in PLAYER_TINTS.iter().enumerate().take(players.0) {
+        let mut bird = commands.spawn(bird_bundle(&asset_server, character));
+        bird.insert(Flappy(player));
+        if players.0 > 1 {
+            bird.insert(Transform::from_xyz(
+                0.0,
+                players::start_height(player, *players),
+                1.0,
+            ));
+            bird.insert(Sprite {
+                color: *tint,
+                custom_size: Some(Vec2::splat(character.sprite_size)),
+                ..default()
+            });
+        }
+    }
+}
 
-    // flappy
-    commands
-        .spawn(SpriteBundle {
-            texture: asset_server.load("bevy.png"),
+/// Sprite and physics of a bird flying as `character`, at the start of the run.
+pub fn bird_bundle(asset_server: &AssetServer, character: &CharacterDefinition) -> impl Bundle {
+    (
+        SpriteBundle {
+            texture: asset_server.load(&character.sprite),
             sprite: Sprite {
-                color: Color::rgb(0.0, 0.0, 0.0),
+                color: character.color(),
+                custom_size: Some(Vec2::splat(character.sprite_size)),
                 ..default()
             },
             transform: Transform::from_xyz(0.0, 0.0, 1.0),
             ..default()
-        })
-        .insert(RigidBody::Dynamic)
-        .insert(Velocity::zero())
-        .insert(ActiveEvents::COLLISION_EVENTS)
-        .insert(Collider::cuboid(sprite_size / 2.0, sprite_size / 2.0))
-        .insert(CollisionGroups::new(
-            Group::from_bits(0b0100).unwrap(),
-            Group::from_bits(0b0100).unwrap(),
-        ))
-        .insert(ExternalImpulse {
+        },
+        RigidBody::Dynamic,
+        Velocity::zero(),
+        ActiveEvents::COLLISION_EVENTS,
+        character.scaled_collider(1.0),
+        collision_groups(FLAPPY_FILTER),
+        ExternalImpulse {
             impulse: Vec2::new(0.0, 0.0),
             torque_impulse: 0.0,
-        })
-        .insert(GravityScale(25.0))
-        .insert(Flappy);
+        },
+        GravityScale(character.gravity_scale),
+        Interpolated::default(),
+        character.clone(),
+        Bird,
+    )
+}
+
+/// Throws the bird upwards, as a flap does.
+pub fn flap_bird(
+    character: &CharacterDefinition,
+    velocity: &mut Velocity,
+    external_impulse: &mut ExternalImpulse,
+    transform: &mut Transform,
+) {
+    external_impulse.impulse = Vec2::new(0.0, character.flap_impulse);
+    velocity.linvel = Vec2::new(0.0, 0.0);
+    if let CharacterRotation::Tilt { flap_angle, .. } = character.rotation {
+        transform.rotation = Quat::from_rotation_z(flap_angle.to_radians());
+    }
 }
 
-fn animate_flappy(mut q_flappy: Query<(&Velocity, &mut Transform), With<Flappy>>) {
-    for (velocity, mut transform) in q_flappy.iter_mut() {
-        if velocity.linvel[1] > 0.0 {
-            transform.rotation = transform
-                .rotation
-                .lerp(Quat::from_rotation_z(-PI / 2.0), 0.02);
+fn collision_groups(filter: u32) -> CollisionGroups {
+    CollisionGroups::new(
+        Group::from_bits(FLAPPY_GROUP).unwrap(),
+        Group::from_bits(filter).unwrap(),
+    )
+}
+
+// picks up edits to the bird's character; a new sprite image shows from the next run
+fn apply_character_tuning(
+    tuning: Res<Tuning>,
+    mut q_flappy: Query<(&mut CharacterDefinition, &mut GravityScale), With<Flappy>>,
+) {
+    for (mut character, mut gravity_scale) in q_flappy.iter_mut() {
+        if let Some(tuned) = tuning.characters.iter().find(|c| c.name == character.name) {
+            *character = tuned.clone();
+            gravity_scale.0 = character.gravity_scale;
+        }
+    }
+}
+
+fn animate_flappy(
+    mut q_flappy: Query<(&Velocity, &CharacterDefinition, &mut Transform), With<Bird>>,
+) {
+    for (velocity, character, mut transform) in q_flappy.iter_mut() {
+        let CharacterRotation::Tilt {
+            rising_rate,
+            falling_rate,
+            ..
+        } = character.rotation
+        else {
+            continue;
+        };
+        let rate = if velocity.linvel.y > 0.0 {
+            rising_rate
         } else {
-            transform.rotation = transform
-                .rotation
-                .lerp(Quat::from_rotation_z(-PI / 2.0), 0.04);
+            falling_rate
+        };
+        transform.rotation = transform
+            .rotation
+            .lerp(Quat::from_rotation_z(-PI / 2.0), rate);
+    }
+}
+
+fn limit_fall_speed(mut q_flappy: Query<(&CharacterDefinition, &mut Velocity), With<Bird>>) {
+    for (character, mut velocity) in q_flappy.iter_mut() {
+        if velocity.linvel.y < -character.max_fall_speed {
+            velocity.linvel.y = -character.max_fall_speed;
         }
     }
 }
@@ -81,70 +248,145 @@ fn despawn_flappy(mut commands: Commands, query: Query<Entity, With<Flappy>>) {
 }
 
 fn in_game_control(
-    mut app_state: ResMut<NextState<AppState>>,
-    mut flappy: Query<(&mut Velocity, &mut ExternalImpulse, &mut Transform), With<Flappy>>,
+    mut pending: ResMut<PendingFlaps>,
+    players: Res<PlayerCount>,
     buttons: Res<Input<MouseButton>>,
     key_buttons: Res<Input<KeyCode>>,
 ) {
-    if buttons.any_just_pressed([MouseButton::Left, MouseButton::Right])
-        || key_buttons.any_just_pressed([KeyCode::Space])
-    {
-        let (mut velocity, mut external_impulse, mut transform) = flappy.single_mut();
-        external_impulse.impulse = Vec2::new(0.0, 800.0);
-        velocity.linvel = Vec2::new(0.0, 0.0);
-        transform.rotation = Quat::from_rotation_z(PI / 3.0);
+    if players.0 == 1 {
+        if buttons.any_just_pressed([MouseButton::Left, MouseButton::Right])
+            || key_buttons.any_just_pressed([KeyCode::Space])
+        {
+            pending.0.push(0);
+        }
+        return;
     }
-    if key_buttons.just_pressed(KeyCode::Escape) {
-        println!("Escape and go to main menu again");
-        app_state.set(AppState::GameStart);
+    for (player, control) in PLAYER_CONTROLS.iter().enumerate().take(players.0) {
+        if control.just_pressed(&key_buttons, &buttons) {
+            pending.0.push(player);
+        }
     }
 }
 
-fn detect_flappy_pipes_collision(
-    mut app_state: ResMut<NextState<AppState>>,
+fn send_pending_flaps(mut pending: ResMut<PendingFlaps>, mut flaps: EventWriter<Flap>) {
+    for player in pending.0.drain(..) {
+        flaps.send(Flap(player));
+    }
+}
+
+fn forget_pending_flaps(mut pending: ResMut<PendingFlaps>) {
+    pending.0.clear();
+}
+
+fn flap(
+    mut flaps: EventReader<Flap>,
+    mut q_flappy: Query<(
+        &Flappy,
+        &CharacterDefinition,
+        &mut Velocity,
+        &mut ExternalImpulse,
+        &mut Transform,
+    )>,
+) {
+    let flapped: Vec<usize> = flaps.read().map(|Flap(player)| *player).collect();
+    if flapped.is_empty() {
+        return;
+    }
+    for (flappy, character, mut velocity, mut external_impulse, mut transform) in
+        q_flappy.iter_mut()
+    {
+        if flapped.contains(&flappy.0) {
+            flap_bird(
+                character,
+                &mut velocity,
+                &mut external_impulse,
+                &mut transform,
+            );
+        }
+    }
+}
+
+// power-ups are shared, so one shield saves every bird that crashes in the step it is used
+pub fn detect_flappy_lethal_collision(
+    mut commands: Commands,
+    mut crashes: EventWriter<Crashed>,
     rapier_context: Res<RapierContext>,
-    query_flappy: Query<Entity, With<Flappy>>,
-    query_pipe: Query<Entity, Or<(With<PipeTop>, With<PipeBottom>)>>,
+    query_flappy: Query<Entity, (With<Flappy>, Without<Invulnerable>)>,
+    query_lethal: Query<Entity, With<Lethal>>,
+    mut power_ups: ResMut<ActivePowerUps>,
 ) {
-    for entity_flappy in query_flappy.iter() {
-        for entity_pipe in query_pipe.iter() {
-            if let Some(_value) = rapier_context.contact_pair(entity_flappy, entity_pipe) {
-                app_state.set(AppState::GameStart);
-            }
+    let hit: Vec<Entity> = query_flappy
+        .iter()
+        .filter(|entity_flappy| {
+            query_lethal.iter().any(|entity_lethal| {
+                rapier_context
+                    .contact_pair(*entity_flappy, entity_lethal)
+                    .is_some()
+            })
+        })
+        .collect();
+    if hit.is_empty() {
+        return;
+    }
+    if power_ups.consume(PowerUpKind::Shield) {
+        for entity_flappy in hit {
+            commands
+                .entity(entity_flappy)
+                .insert(Invulnerable::bundle(SHIELD_GRACE_SECS));
+        }
+    } else {
+        for entity_flappy in hit {
+            crashes.send(Crashed(entity_flappy));
+        }
+    }
+}
+
+fn tick_invulnerability(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_flappy: Query<(Entity, &mut Invulnerable, &mut Visibility)>,
+) {
+    for (entity, mut invulnerable, mut visibility) in q_flappy.iter_mut() {
+        if invulnerable.timer.tick(time.delta()).finished() {
+            *visibility = Visibility::Inherited;
+            commands
+                .entity(entity)
+                .remove::<Invulnerable>()
+                .insert(collision_groups(FLAPPY_FILTER));
+        } else if invulnerable.blink.tick(time.delta()).just_finished() {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
         }
     }
 }
 
 fn detect_flappy_gap_sensor_collision(
     mut collision_events: EventReader<CollisionEvent>,
-    query_flappy: Query<Entity, With<Flappy>>,
-    mut query_gap_sensor: Query<(Entity, &mut GapSensor), With<GapSensor>>,
+    query_flappy: Query<&Flappy>,
+    mut query_gap_sensor: Query<&mut GapSensor>,
     mut score: ResMut<Score>,
+    mut scoreboard: ResMut<Scoreboard>,
+    score_multiplier: Res<ScoreMultiplier>,
 ) {
-    for collision_event in collision_events.iter() {
-        match collision_event {
-            CollisionEvent::Started(entity1, entity2, _value) => {
-                if let (Ok(_value), Ok(_value2)) =
-                    (query_flappy.get(*entity1), query_gap_sensor.get(*entity2))
-                {
-                    let (_entity, mut sensor) = query_gap_sensor.get_mut(*entity2).unwrap();
-                    if !sensor.counted {
-                        sensor.counted = true;
-                        score.0 += 1;
-                    }
-                }
-
-                if let (Ok(_value), Ok(_value2)) =
-                    (query_flappy.get(*entity2), query_gap_sensor.get(*entity1))
-                {
-                    let (_entity, mut sensor) = query_gap_sensor.get_mut(*entity1).unwrap();
-                    if !sensor.counted {
-                        sensor.counted = true;
-                        score.0 += 1;
-                    }
-                }
+    for collision_event in collision_events.read() {
+        let CollisionEvent::Started(entity1, entity2, _) = *collision_event else {
+            continue;
+        };
+        for (entity_flappy, entity_sensor) in [(entity1, entity2), (entity2, entity1)] {
+            let (Ok(flappy), Ok(mut sensor)) = (
+                query_flappy.get(entity_flappy),
+                query_gap_sensor.get_mut(entity_sensor),
+            ) else {
+                continue;
+            };
+            // every player scores each gap once
+            if !sensor.counted[flappy.0] {
+                sensor.counted[flappy.0] = true;
+                let player_score = scoreboard.award(flappy.0, score_multiplier.0);
+                score.0 = score.0.max(player_score);
             }
-            _ => (),
         }
     }
 }
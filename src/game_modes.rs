@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::difficulty::{self, DifficultyCurve};
+use crate::levels::LevelRun;
+use crate::lives::{self, Lives};
+use crate::pause::PauseState;
+use crate::tuning::Tuning;
+use crate::AppState;
+use crate::SimulationSet;
+use crate::SimulationTick;
+use crate::SIMULATION_HZ;
+
+/// Sets up each run for the chosen [`GameMode`] and ends timed runs when the clock runs out.
+pub struct GameModesPlugin;
+
+impl Plugin for GameModesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameMode>()
+            .add_systems(
+                OnEnter(AppState::InGame),
+                apply_game_mode
+                    .after(difficulty::select_curve)
+                    .after(lives::reset_lives),
+            )
+            .add_systems(
+                FixedUpdate,
+                end_timed_run
+                    .in_set(SimulationSet::Gameplay)
+                    .run_if(resource_equals(GameMode::Timed))
+                    .run_if(not(resource_exists::<LevelRun>()))
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+/// The rules the next run is played by. Each keeps its own high score.
+#[derive(
+    Resource, Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord,
+)]
+pub enum GameMode {
+    /// The classic game: it gets harder as the score grows and ends at the first crash.
+    #[default]
+    Endless,
+    /// As many points as possible before the clock runs out.
+    Timed,
+    /// Crashes respawn the bird and the pipes stay easy.
+    Zen,
+    /// The hardest pipes from the start, and a single life.
+    Hardcore,
+}
+
+impl GameMode {
+    /// Every mode, in the order the main menu cycles through them.
+    pub const ALL: [Self; 4] = [Self::Endless, Self::Timed, Self::Zen, Self::Hardcore];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Endless => "Endless",
+            Self::Timed => "Timed",
+            Self::Zen => "Zen",
+            Self::Hardcore => "Hardcore",
+        }
+    }
+
+    /// The mode called `name`, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Position in [`GameMode::ALL`], which replay files store the mode as.
+    pub fn index(self) -> u8 {
+        Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0) as u8
+    }
+
+    pub fn from_index(index: u8) -> Option<Self> {
+        Self::ALL.get(usize::from(index)).copied()
+    }
+}
+
+/// Simulation ticks left in a timed run.
+pub fn ticks_left(tuning: &Tuning, tick: &SimulationTick) -> u64 {
+    let total = (f64::from(tuning.modes.timed_secs) * SIMULATION_HZ).round() as u64;
+    total.saturating_sub(tick.0)
+}
+
+pub fn apply_game_mode(
+    mode: Res<GameMode>,
+    tuning: Res<Tuning>,
+    mut curve: ResMut<DifficultyCurve>,
+    mut lives: ResMut<Lives>,
+) {
+    match *mode {
+        GameMode::Endless | GameMode::Timed => {}
+        GameMode::Zen => {
+            *curve = tuning.modes.zen_curve.clone();
+            lives.unlimited = true;
+        }
+        GameMode::Hardcore => {
+            *curve = tuning.modes.hardcore_curve.clone();
+            lives.remaining = 1;
+        }
+    }
+}
+
+fn end_timed_run(
+    tuning: Res<Tuning>,
+    tick: Res<SimulationTick>,
+    mut app_state: ResMut<NextState<AppState>>,
+) {
+    if ticks_left(&tuning, &tick) == 0 {
+        app_state.set(AppState::GameOver);
+    }
+}
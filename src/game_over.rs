@@ -0,0 +1,194 @@
+use bevy::prelude::*;
+
+use crate::game_modes::GameMode;
+use crate::levels::LevelResult;
+use crate::players::{PlayerCount, Scoreboard, PLAYER_TINTS};
+use crate::save::PlayerStats;
+use crate::widgets::spawn_button;
+use crate::AppState;
+use crate::RunTime;
+use crate::Score;
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::GameOver), spawn_game_over_menu)
+            .add_systems(
+                Update,
+                (game_over_buttons, game_over_keys).run_if(in_state(AppState::GameOver)),
+            )
+            .add_systems(OnExit(AppState::GameOver), despawn_game_over_menu);
+    }
+}
+
+#[derive(Component)]
+struct GameOverMenu;
+
+#[derive(Component, Clone, Copy)]
+enum GameOverAction {
+    Retry,
+    Menu,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_game_over_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    score: Res<Score>,
+    stats: Res<PlayerStats>,
+    mode: Res<GameMode>,
+    run_time: Res<RunTime>,
+    level_result: Option<Res<LevelResult>>,
+    players: Res<PlayerCount>,
+    scoreboard: Res<Scoreboard>,
+) {
+    let font = asset_server.load("fonts/Monocraft.otf");
+    let text_style = TextStyle {
+        font: font.clone(),
+        font_size: 25.0,
+        color: Color::rgb(0.086, 0.086, 0.086),
+    };
+    let text_margin = Style {
+        margin: UiRect::all(Val::Px(5.0)),
+        ..default()
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(GameOverMenu)
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    if level_result.is_some() {
+                        "Level Complete"
+                    } else {
+                        "Game Over"
+                    },
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 50.0,
+                        color: Color::hsl(153.0, 0.67, 0.28),
+                    },
+                )
+                .with_style(text_margin.clone()),
+            );
+            if players.0 > 1 {
+                let timed = *mode == GameMode::Timed && level_result.is_none();
+                let (result, color) = match scoreboard.winner(timed) {
+                    Some(winner) => (format!("Player {} wins!", winner + 1), PLAYER_TINTS[winner]),
+                    None => ("Draw".to_string(), text_style.color),
+                };
+                parent.spawn(
+                    TextBundle::from_section(
+                        result,
+                        TextStyle {
+                            color,
+                            ..text_style.clone()
+                        },
+                    )
+                    .with_style(text_margin.clone()),
+                );
+                parent.spawn(
+                    TextBundle::from_sections(
+                        scoreboard.scores.iter().zip(PLAYER_TINTS).enumerate().map(
+                            |(player, (score, tint))| {
+                                TextSection::new(
+                                    format!("P{}: {score}  ", player + 1),
+                                    TextStyle {
+                                        color: tint,
+                                        ..text_style.clone()
+                                    },
+                                )
+                            },
+                        ),
+                    )
+                    .with_style(text_margin.clone()),
+                );
+            } else {
+                parent.spawn(
+                    TextBundle::from_section(format!("Score: {}", score.0), text_style.clone())
+                        .with_style(text_margin.clone()),
+                );
+            }
+            if let Some(result) = &level_result {
+                parent.spawn(
+                    TextBundle::from_section(
+                        format!(
+                            "Stars: {}/3  Coins: {}/{}  Deaths: {}",
+                            result.stars, result.coins, result.coin_count, result.deaths
+                        ),
+                        text_style.clone(),
+                    )
+                    .with_style(text_margin.clone()),
+                );
+            } else {
+                parent.spawn(
+                    TextBundle::from_section(
+                        format!("{} best: {}", mode.name(), stats.best_score(*mode)),
+                        text_style.clone(),
+                    )
+                    .with_style(text_margin.clone()),
+                );
+            }
+            parent.spawn(
+                TextBundle::from_section(
+                    format!("Time: {:.1}s", run_time.0.elapsed_secs()),
+                    text_style.clone(),
+                )
+                .with_style(text_margin.clone()),
+            );
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        margin: UiRect::top(Val::Px(20.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    spawn_button(parent, &font, "Retry", GameOverAction::Retry);
+                    spawn_button(parent, &font, "Menu", GameOverAction::Menu);
+                });
+        });
+}
+
+fn game_over_buttons(
+    mut app_state: ResMut<NextState<AppState>>,
+    q_buttons: Query<(&Interaction, &GameOverAction), Changed<Interaction>>,
+) {
+    for (interaction, action) in q_buttons.iter() {
+        if *interaction == Interaction::Pressed {
+            match action {
+                GameOverAction::Retry => app_state.set(AppState::InGame),
+                GameOverAction::Menu => app_state.set(AppState::GameStart),
+            }
+        }
+    }
+}
+
+fn game_over_keys(mut app_state: ResMut<NextState<AppState>>, key_buttons: Res<Input<KeyCode>>) {
+    if key_buttons.just_pressed(KeyCode::Space) {
+        app_state.set(AppState::InGame);
+    }
+    if key_buttons.just_pressed(KeyCode::Escape) {
+        app_state.set(AppState::GameStart);
+    }
+}
+
+fn despawn_game_over_menu(mut commands: Commands, query: Query<Entity, With<GameOverMenu>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
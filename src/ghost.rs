@@ -0,0 +1,288 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, mem, path::PathBuf};
+
+use crate::characters::SelectedCharacter;
+use crate::flappy::Flappy;
+use crate::game_modes::GameMode;
+use crate::interpolation::Interpolated;
+use crate::levels::LevelRun;
+use crate::pause::PauseState;
+use crate::players::PlayerCount;
+use crate::rng::{self, GameRng};
+use crate::save::SAVE_DIRECTORY;
+use crate::tuning::Tuning;
+use crate::AppState;
+use crate::Score;
+use crate::SimulationSet;
+use crate::SimulationTick;
+
+const GHOSTS_VERSION: u32 = 1;
+const GHOSTS_FILE_NAME: &str = "ghosts.ron";
+// the best scoring seeds are kept, so the ghosts file stays small
+const MAX_GHOSTS_PER_MODE: usize = 20;
+const GHOST_ALPHA: f32 = 0.35;
+// just behind the live bird
+const GHOST_Z: f32 = 0.9;
+
+/// Records the bird's path through every single-player run, keeps the best run's path for
+/// each seed in a ghosts file, and flies it as a translucent ghost on later runs with that seed.
+/// Only runs that scored are kept, and only the best scoring seeds of each game mode.
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GhostsPath(
+            dirs::data_dir().map(|dir| dir.join(SAVE_DIRECTORY).join(GHOSTS_FILE_NAME)),
+        ))
+        .init_resource::<Ghosts>()
+        .init_resource::<GhostRecording>()
+        .add_systems(PreStartup, load_ghosts)
+        .add_systems(
+            OnEnter(AppState::InGame),
+            start_ghost
+                .after(rng::reseed)
+                .run_if(resource_equals(PlayerCount(1)))
+                .run_if(not(resource_exists::<LevelRun>())),
+        )
+        .add_systems(
+            FixedUpdate,
+            (record_path, fly_ghost)
+                .in_set(SimulationSet::Gameplay)
+                .run_if(resource_equals(PlayerCount(1)))
+                .run_if(not(resource_exists::<LevelRun>()))
+                .run_if(in_state(AppState::InGame))
+                .run_if(in_state(PauseState::Running)),
+        )
+        .add_systems(
+            OnExit(AppState::InGame),
+            (
+                (keep_best_path, write_ghosts)
+                    .chain()
+                    .run_if(resource_equals(PlayerCount(1)))
+                    .run_if(not(resource_exists::<LevelRun>())),
+                despawn_ghost,
+            ),
+        );
+    }
+}
+
+/// Where the ghosts file lives. `None` when the platform has no data directory, in
+/// which case ghosts only last until the game quits.
+#[derive(Resource)]
+pub struct GhostsPath(pub Option<PathBuf>);
+
+/// The best run's path for each seed played, in each game mode.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Ghosts(pub BTreeMap<GameMode, BTreeMap<u64, Ghost>>);
+
+/// A bird's path through a run, for a ghost to fly it again.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Ghost {
+    pub score: u32,
+    /// Name of the character flown.
+    pub character: String,
+    /// Height and angle of the bird at each simulation tick.
+    pub path: Vec<(f32, f32)>,
+}
+
+impl Ghosts {
+    pub fn get(&self, mode: GameMode, seed: u64) -> Option<&Ghost> {
+        self.0.get(&mode)?.get(&seed)
+    }
+}
+
+/// The path the bird has flown so far this run.
+#[derive(Resource, Default)]
+struct GhostRecording {
+    mode: GameMode,
+    seed: u64,
+    character: String,
+    path: Vec<(f32, f32)>,
+}
+
+/// A translucent bird flying a stored path. It has no collider.
+#[derive(Component)]
+pub struct GhostBird {
+    path: Vec<(f32, f32)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct GhostsFile {
+    version: u32,
+    #[serde(default)]
+    ghosts: Ghosts,
+}
+
+fn read_ghosts(path: &PathBuf) -> Result<Option<Ghosts>, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error.to_string()),
+    };
+    let file: GhostsFile = ron::from_str(&contents).map_err(|error| error.to_string())?;
+    if file.version > GHOSTS_VERSION {
+        return Err(format!(
+            "ghosts version {} is newer than the supported version {GHOSTS_VERSION}",
+            file.version
+        ));
+    }
+    Ok(Some(file.ghosts))
+}
+
+fn write_ghosts_file(path: &PathBuf, ghosts: &Ghosts) -> Result<(), String> {
+    let file = GhostsFile {
+        version: GHOSTS_VERSION,
+        ghosts: ghosts.clone(),
+    };
+    let contents = ron::to_string(&file).map_err(|error| error.to_string())?;
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory).map_err(|error| error.to_string())?;
+    }
+    // write next to the file and swap it in, so a crash mid-write can't lose it
+    let temporary_path = path.with_extension("ron.tmp");
+    fs::write(&temporary_path, contents).map_err(|error| error.to_string())?;
+    fs::rename(&temporary_path, path).map_err(|error| error.to_string())
+}
+
+fn load_ghosts(mut ghosts_path: ResMut<GhostsPath>, mut ghosts: ResMut<Ghosts>) {
+    let Some(path) = &ghosts_path.0 else {
+        return;
+    };
+    match read_ghosts(path) {
+        Ok(Some(loaded)) => *ghosts = loaded,
+        Ok(None) => {}
+        Err(error) => {
+            // leave the file alone rather than overwrite it with fresh ghosts
+            warn!(
+                "could not load ghosts file {}, ghosts will not be saved: {error}",
+                path.display()
+            );
+            ghosts_path.0 = None;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_ghost(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    tuning: Res<Tuning>,
+    selected: Res<SelectedCharacter>,
+    game_rng: Res<GameRng>,
+    mode: Res<GameMode>,
+    ghosts: Res<Ghosts>,
+    mut recording: ResMut<GhostRecording>,
+) {
+    *recording = GhostRecording {
+        mode: *mode,
+        seed: game_rng.seed(),
+        character: tuning.character(&selected.0).name.clone(),
+        path: Vec::new(),
+    };
+    let Some(ghost) = ghosts.get(*mode, game_rng.seed()) else {
+        return;
+    };
+    let character = tuning.character(&ghost.character);
+    commands
+        .spawn(SpriteBundle {
+            texture: asset_server.load(&character.sprite),
+            sprite: Sprite {
+                color: character.color().with_a(GHOST_ALPHA),
+                custom_size: Some(Vec2::splat(character.sprite_size)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, 0.0, GHOST_Z),
+            ..default()
+        })
+        .insert(Interpolated::default())
+        .insert(GhostBird {
+            path: ghost.path.clone(),
+        });
+}
+
+fn record_path(mut recording: ResMut<GhostRecording>, q_flappy: Query<&Transform, With<Flappy>>) {
+    let pose = match q_flappy.get_single() {
+        Ok(transform) => (
+            transform.translation.y,
+            transform.rotation.to_euler(EulerRot::ZYX).0,
+        ),
+        // keeps the path in step with the ticks while there is no bird
+        Err(_) => recording.path.last().copied().unwrap_or_default(),
+    };
+    recording.path.push(pose);
+}
+
+fn fly_ghost(
+    mut commands: Commands,
+    tick: Res<SimulationTick>,
+    mut q_ghost: Query<(Entity, &GhostBird, &mut Transform)>,
+) {
+    for (entity, ghost, mut transform) in q_ghost.iter_mut() {
+        // the path starts at the run's first tick, tick 1
+        match ghost.path.get(tick.0.saturating_sub(1) as usize) {
+            Some(&(height, angle)) => {
+                transform.translation.y = height;
+                transform.rotation = Quat::from_rotation_z(angle);
+            }
+            // the ghost's run ended here
+            None => commands.entity(entity).despawn_recursive(),
+        }
+    }
+}
+
+fn keep_best_path(
+    score: Res<Score>,
+    mut recording: ResMut<GhostRecording>,
+    mut ghosts: ResMut<Ghosts>,
+) {
+    if score.0 == 0 || recording.path.is_empty() {
+        return;
+    }
+    let kept = ghosts.0.get(&recording.mode);
+    let mut evicted = None;
+    match kept.and_then(|seeds| seeds.get(&recording.seed)) {
+        Some(ghost) if score.0 <= ghost.score => return,
+        Some(_) => {}
+        None => {
+            let full = kept.filter(|seeds| seeds.len() >= MAX_GHOSTS_PER_MODE);
+            // make room by dropping the lowest scoring seed, unless this run is lower still
+            if let Some((&lowest_seed, lowest)) =
+                full.and_then(|seeds| seeds.iter().min_by_key(|(_, ghost)| ghost.score))
+            {
+                if score.0 <= lowest.score {
+                    return;
+                }
+                evicted = Some(lowest_seed);
+            }
+        }
+    }
+    let ghost = Ghost {
+        score: score.0,
+        character: mem::take(&mut recording.character),
+        path: mem::take(&mut recording.path),
+    };
+    let seeds = ghosts.0.entry(recording.mode).or_default();
+    if let Some(seed) = evicted {
+        seeds.remove(&seed);
+    }
+    seeds.insert(recording.seed, ghost);
+}
+
+fn despawn_ghost(mut commands: Commands, query: Query<Entity, With<GhostBird>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn write_ghosts(ghosts_path: Res<GhostsPath>, ghosts: Res<Ghosts>) {
+    let Some(path) = &ghosts_path.0 else {
+        return;
+    };
+    if !ghosts.is_changed() {
+        return;
+    }
+    if let Err(error) = write_ghosts_file(path, &ghosts) {
+        warn!("could not write ghosts file {}: {error}", path.display());
+    }
+}
@@ -0,0 +1,68 @@
+use bevy::{
+    app::ScheduleRunnerPlugin, asset::AssetPlugin, input::InputPlugin, prelude::*,
+    time::TimeUpdateStrategy, window::ExitCondition,
+};
+use std::time::Duration;
+
+use crate::playfield::Playfield;
+use crate::tuning::Tuning;
+use crate::AppState;
+use crate::GamePlugin;
+use crate::SIMULATION_HZ;
+
+const HEADLESS_FRAME_RATE: f64 = 60.0;
+// long enough for the tuning file to load from disk
+const LOAD_FRAMES: usize = 600;
+
+/// Builds an app that runs the whole simulation without a window or renderer.
+///
+/// The spawn systems read their bounds from the given [`Playfield`] instead of the
+/// primary window, so the game behaves as if it had a window of that size.
+pub fn app(playfield: Playfield) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(
+            1.0 / HEADLESS_FRAME_RATE,
+        ))),
+        TransformPlugin,
+        HierarchyPlugin,
+        InputPlugin,
+        WindowPlugin {
+            primary_window: None,
+            exit_condition: ExitCondition::DontExit,
+            close_when_requested: false,
+        },
+        AssetPlugin::default(),
+    ))
+    // sprites and rapier still hold handles to these, even though nothing renders them
+    .init_asset::<Image>()
+    .init_asset::<Mesh>()
+    .insert_resource(playfield)
+    .add_plugins(GamePlugin);
+    app
+}
+
+/// Builds a headless app that simulates exactly one fixed tick per [`App::update`], as fast
+/// as it is stepped, and steps it until the tuning file has loaded.
+pub fn stepped_app(playfield: Playfield) -> App {
+    let mut app = self::app(playfield);
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1.0 / SIMULATION_HZ,
+    )));
+    // the first frame only sets the clock
+    app.update();
+    // runs should all play by the file's tuning, not start on the defaults
+    for _ in 0..LOAD_FRAMES {
+        if !app.world.resource::<Assets<Tuning>>().is_empty() {
+            break;
+        }
+        app.update();
+    }
+    app.update();
+    app
+}
+
+/// Starts a run right away, since there is no menu to click through without a window.
+pub fn start_run(mut app_state: ResMut<NextState<AppState>>) {
+    app_state.set(AppState::InGame);
+}
@@ -1,18 +1,43 @@
 use bevy::prelude::*;
 
+use crate::coins::Coins;
+use crate::game_modes::{self, GameMode};
+use crate::levels::{self, LevelRun};
+use crate::lives::Lives;
+use crate::players::{PlayerCount, Scoreboard, PLAYER_TINTS};
+use crate::power_ups::{ActivePowerUps, PowerUpRegistry};
+use crate::rng::{self, GameRng};
+use crate::save::PlayerStats;
+use crate::tuning::Tuning;
 use crate::AppState;
 use crate::Score;
+use crate::SimulationTick;
+use crate::SIMULATION_HZ;
 
 pub struct InGameUiPlugin;
 
 impl Plugin for InGameUiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(AppState::InGame), spawn_score_menu)
-            .add_systems(
-                Update,
-                detect_score_change.run_if(in_state(AppState::InGame)),
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            spawn_score_menu
+                .after(rng::reseed)
+                .after(levels::start_level)
+                .after(game_modes::apply_game_mode),
+        )
+        .add_systems(
+            Update,
+            (
+                detect_score_change,
+                detect_coins_change,
+                show_active_power_ups,
+                detect_lives_change,
+                show_time_left,
+                show_player_scores,
             )
-            .add_systems(OnExit(AppState::InGame), despawn);
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(OnExit(AppState::InGame), despawn);
     }
 }
 
@@ -22,7 +47,32 @@ struct ScoreMenu;
 #[derive(Component)]
 struct ScoreText;
 
-fn spawn_score_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+#[derive(Component)]
+struct CoinsText;
+
+#[derive(Component)]
+struct PowerUpsText;
+
+#[derive(Component)]
+struct LivesText;
+
+#[derive(Component)]
+struct TimeLeftText;
+
+#[derive(Component)]
+struct PlayerScoresText;
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_score_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_rng: Res<GameRng>,
+    stats: Res<PlayerStats>,
+    lives: Res<Lives>,
+    mode: Res<GameMode>,
+    players: Res<PlayerCount>,
+    level_run: Option<Res<LevelRun>>,
+) {
     commands
         .spawn(NodeBundle {
             style: Style {
@@ -60,6 +110,144 @@ fn spawn_score_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                     }),
                 )
                 .insert(ScoreText);
+            parent
+                .spawn(
+                    TextBundle::from_sections([
+                        TextSection::new(
+                            "Coins: ",
+                            TextStyle {
+                                font: asset_server.load("fonts/Monocraft.otf"),
+                                font_size: 20.0,
+                                color: Color::rgb(0.086, 0.086, 0.086),
+                            },
+                        ),
+                        TextSection::from_style(TextStyle {
+                            font: asset_server.load("fonts/Monocraft.otf"),
+                            font_size: 20.0,
+                            color: Color::rgb(0.086, 0.086, 0.086),
+                        }),
+                    ])
+                    .with_style(Style {
+                        margin: UiRect::all(Val::Px(5.0)),
+                        ..default()
+                    }),
+                )
+                .insert(CoinsText);
+            if players.0 > 1 {
+                parent
+                    .spawn(
+                        TextBundle::from_sections(PLAYER_TINTS[..players.0].iter().map(|tint| {
+                            TextSection::from_style(TextStyle {
+                                font: asset_server.load("fonts/Monocraft.otf"),
+                                font_size: 20.0,
+                                color: *tint,
+                            })
+                        }))
+                        .with_style(Style {
+                            margin: UiRect::all(Val::Px(5.0)),
+                            ..default()
+                        }),
+                    )
+                    .insert(PlayerScoresText);
+            }
+            // the classic single-life game has nothing to show, and multiplayer birds have one life
+            if lives.remaining > 1 && !lives.unlimited && players.0 == 1 {
+                parent
+                    .spawn(
+                        TextBundle::from_sections([
+                            TextSection::new(
+                                "Lives: ",
+                                TextStyle {
+                                    font: asset_server.load("fonts/Monocraft.otf"),
+                                    font_size: 20.0,
+                                    color: Color::rgb(0.086, 0.086, 0.086),
+                                },
+                            ),
+                            TextSection::new(
+                                lives.remaining.to_string(),
+                                TextStyle {
+                                    font: asset_server.load("fonts/Monocraft.otf"),
+                                    font_size: 20.0,
+                                    color: Color::rgb(0.086, 0.086, 0.086),
+                                },
+                            ),
+                        ])
+                        .with_style(Style {
+                            margin: UiRect::all(Val::Px(5.0)),
+                            ..default()
+                        }),
+                    )
+                    .insert(LivesText);
+            }
+            if *mode == GameMode::Timed && level_run.is_none() {
+                parent
+                    .spawn(
+                        TextBundle::from_sections([
+                            TextSection::new(
+                                "Time: ",
+                                TextStyle {
+                                    font: asset_server.load("fonts/Monocraft.otf"),
+                                    font_size: 20.0,
+                                    color: Color::rgb(0.086, 0.086, 0.086),
+                                },
+                            ),
+                            TextSection::from_style(TextStyle {
+                                font: asset_server.load("fonts/Monocraft.otf"),
+                                font_size: 20.0,
+                                color: Color::rgb(0.086, 0.086, 0.086),
+                            }),
+                        ])
+                        .with_style(Style {
+                            margin: UiRect::all(Val::Px(5.0)),
+                            ..default()
+                        }),
+                    )
+                    .insert(TimeLeftText);
+            }
+            parent
+                .spawn(
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: asset_server.load("fonts/Monocraft.otf"),
+                            font_size: 20.0,
+                            color: Color::rgb(0.086, 0.086, 0.086),
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect::all(Val::Px(5.0)),
+                        ..default()
+                    }),
+                )
+                .insert(PowerUpsText);
+            parent.spawn(
+                TextBundle::from_section(
+                    format!("Best: {}", stats.best_score(*mode)),
+                    TextStyle {
+                        font: asset_server.load("fonts/Monocraft.otf"),
+                        font_size: 20.0,
+                        color: Color::rgb(0.086, 0.086, 0.086),
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(5.0)),
+                    ..default()
+                }),
+            );
+            parent.spawn(
+                TextBundle::from_section(
+                    format!("Seed: {}", game_rng.seed()),
+                    TextStyle {
+                        font: asset_server.load("fonts/Monocraft.otf"),
+                        font_size: 14.0,
+                        color: Color::rgb(0.086, 0.086, 0.086),
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(5.0)),
+                    ..default()
+                }),
+            );
         });
 }
 
@@ -71,12 +259,74 @@ fn detect_score_change(score: Res<Score>, mut q_text: Query<&mut Text, With<Scor
     }
 }
 
-fn despawn(
-    mut commands: Commands,
-    query: Query<Entity, With<ScoreMenu>>,
-    mut score: ResMut<Score>,
+fn detect_coins_change(coins: Res<Coins>, mut q_text: Query<&mut Text, With<CoinsText>>) {
+    if coins.is_changed() {
+        for mut text in q_text.iter_mut() {
+            text.sections[1].value = coins.0.to_string();
+        }
+    }
+}
+
+fn detect_lives_change(lives: Res<Lives>, mut q_text: Query<&mut Text, With<LivesText>>) {
+    if lives.is_changed() {
+        for mut text in q_text.iter_mut() {
+            text.sections[1].value = lives.remaining.to_string();
+        }
+    }
+}
+
+fn show_time_left(
+    tuning: Res<Tuning>,
+    tick: Res<SimulationTick>,
+    mut q_text: Query<&mut Text, With<TimeLeftText>>,
+) {
+    if tick.is_changed() {
+        let secs = game_modes::ticks_left(&tuning, &tick) as f64 / SIMULATION_HZ;
+        for mut text in q_text.iter_mut() {
+            text.sections[1].value = format!("{}s", secs.ceil());
+        }
+    }
+}
+
+fn show_player_scores(
+    scoreboard: Res<Scoreboard>,
+    mut q_text: Query<&mut Text, With<PlayerScoresText>>,
+) {
+    if scoreboard.is_changed() {
+        for mut text in q_text.iter_mut() {
+            for (player, section) in text.sections.iter_mut().enumerate() {
+                let score = scoreboard.scores.get(player).copied().unwrap_or(0);
+                let out = if scoreboard.flying.get(player) == Some(&false) {
+                    " (out)"
+                } else {
+                    ""
+                };
+                section.value = format!("P{}: {score}{out}  ", player + 1);
+            }
+        }
+    }
+}
+
+fn show_active_power_ups(
+    active: Res<ActivePowerUps>,
+    registry: Res<PowerUpRegistry>,
+    mut q_text: Query<&mut Text, With<PowerUpsText>>,
 ) {
-    score.0 = 0;
+    if active.is_changed() {
+        let labels: Vec<String> = active
+            .remaining()
+            .filter_map(|(kind, remaining)| {
+                let effect = registry.get(kind)?;
+                Some(format!("{} {:.1}s", effect.label, remaining.as_secs_f32()))
+            })
+            .collect();
+        for mut text in q_text.iter_mut() {
+            text.sections[0].value = labels.join("  ");
+        }
+    }
+}
+
+fn despawn(mut commands: Commands, query: Query<Entity, With<ScoreMenu>>) {
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
     }
@@ -0,0 +1,62 @@
+use bevy::{prelude::*, transform::TransformSystem};
+use bevy_rapier2d::prelude::*;
+
+use crate::SimulationSet;
+
+/// Smooths the movement of simulated entities on frames that fall between two
+/// [`FixedUpdate`] steps. Only the renderer needs this, the headless app skips it.
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                restore_simulated_transforms.before(SimulationSet::Tick),
+                store_simulated_transforms.after(PhysicsSet::Writeback),
+            ),
+        )
+        .add_systems(
+            PostUpdate,
+            interpolate_transforms.before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// The last two poses the simulation produced for an entity.
+///
+/// Between steps its `Transform` holds a blend of the two; each step puts the latest
+/// pose back first, so gameplay and physics never see the blended one.
+#[derive(Component, Default)]
+pub struct Interpolated {
+    previous: Option<Transform>,
+    current: Option<Transform>,
+}
+
+fn restore_simulated_transforms(mut query: Query<(&Interpolated, &mut Transform)>) {
+    for (interpolated, mut transform) in query.iter_mut() {
+        if let Some(current) = interpolated.current {
+            *transform = current;
+        }
+    }
+}
+
+fn store_simulated_transforms(mut query: Query<(&mut Interpolated, &Transform)>) {
+    for (mut interpolated, transform) in query.iter_mut() {
+        interpolated.previous = Some(interpolated.current.unwrap_or(*transform));
+        interpolated.current = Some(*transform);
+    }
+}
+
+fn interpolate_transforms(
+    fixed_time: Res<Time<Fixed>>,
+    mut query: Query<(&Interpolated, &mut Transform)>,
+) {
+    let blend = fixed_time.overstep_percentage();
+    for (interpolated, mut transform) in query.iter_mut() {
+        if let (Some(previous), Some(current)) = (interpolated.previous, interpolated.current) {
+            transform.translation = previous.translation.lerp(current.translation, blend);
+            transform.rotation = previous.rotation.slerp(current.rotation, blend);
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+
+use crate::levels::{BuiltInLevels, CurrentLevel, Level, BUILT_IN_LEVELS};
+use crate::save::PlayerStats;
+use crate::widgets::spawn_button;
+use crate::AppState;
+
+const LEVEL_KEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+/// Lists the built-in levels with the stars earned on each, and starts the one picked.
+pub struct LevelSelectPlugin;
+
+impl Plugin for LevelSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::LevelSelect), spawn_level_select)
+            .add_systems(
+                Update,
+                (level_select_buttons, level_select_keys, name_loaded_levels)
+                    .run_if(in_state(AppState::LevelSelect)),
+            )
+            .add_systems(OnExit(AppState::LevelSelect), despawn_level_select);
+    }
+}
+
+#[derive(Component)]
+struct LevelSelectMenu;
+
+/// The line describing the built-in level at this index.
+#[derive(Component)]
+struct LevelLine(usize);
+
+#[derive(Component, Clone, Copy)]
+enum LevelSelectAction {
+    Play(usize),
+    Back,
+}
+
+fn spawn_level_select(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    built_in: Res<BuiltInLevels>,
+    levels: Res<Assets<Level>>,
+    stats: Res<PlayerStats>,
+) {
+    let font = asset_server.load("fonts/Monocraft.otf");
+    let text_style = TextStyle {
+        font: font.clone(),
+        font_size: 20.0,
+        color: Color::rgb(0.086, 0.086, 0.086),
+    };
+    let text_margin = Style {
+        margin: UiRect::all(Val::Px(5.0)),
+        ..default()
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(LevelSelectMenu)
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    "Levels",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 50.0,
+                        color: Color::hsl(153.0, 0.67, 0.28),
+                    },
+                )
+                .with_style(text_margin.clone()),
+            );
+            for (index, handle) in built_in.0.iter().enumerate() {
+                parent
+                    .spawn(
+                        TextBundle::from_section(
+                            describe(index, levels.get(handle), &stats),
+                            text_style.clone(),
+                        )
+                        .with_style(text_margin.clone()),
+                    )
+                    .insert(LevelLine(index));
+            }
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        margin: UiRect::top(Val::Px(20.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for index in 0..built_in.0.len() {
+                        spawn_button(
+                            parent,
+                            &font,
+                            &(index + 1).to_string(),
+                            LevelSelectAction::Play(index),
+                        );
+                    }
+                    spawn_button(parent, &font, "Back", LevelSelectAction::Back);
+                });
+        });
+}
+
+fn describe(index: usize, level: Option<&Level>, stats: &PlayerStats) -> String {
+    let name = level.map_or("...", |level| level.name.as_str());
+    let stars = stats
+        .level_stars
+        .get(BUILT_IN_LEVELS[index])
+        .copied()
+        .unwrap_or(0);
+    format!("{}. {name}  Stars: {stars}/3", index + 1)
+}
+
+// the names aren't known until the files have loaded
+fn name_loaded_levels(
+    mut level_events: EventReader<AssetEvent<Level>>,
+    built_in: Res<BuiltInLevels>,
+    levels: Res<Assets<Level>>,
+    stats: Res<PlayerStats>,
+    mut q_lines: Query<(&LevelLine, &mut Text)>,
+) {
+    if level_events.read().count() == 0 {
+        return;
+    }
+    for (LevelLine(index), mut text) in q_lines.iter_mut() {
+        text.sections[0].value = describe(*index, levels.get(&built_in.0[*index]), &stats);
+    }
+}
+
+fn play(index: usize, current_level: &mut CurrentLevel, app_state: &mut NextState<AppState>) {
+    current_level.0 = Some(BUILT_IN_LEVELS[index].to_string());
+    app_state.set(AppState::CharacterSelect);
+}
+
+fn level_select_buttons(
+    mut app_state: ResMut<NextState<AppState>>,
+    mut current_level: ResMut<CurrentLevel>,
+    q_buttons: Query<(&Interaction, &LevelSelectAction), Changed<Interaction>>,
+) {
+    for (interaction, action) in q_buttons.iter() {
+        if *interaction == Interaction::Pressed {
+            match action {
+                LevelSelectAction::Play(index) => play(*index, &mut current_level, &mut app_state),
+                LevelSelectAction::Back => app_state.set(AppState::GameStart),
+            }
+        }
+    }
+}
+
+fn level_select_keys(
+    mut app_state: ResMut<NextState<AppState>>,
+    mut current_level: ResMut<CurrentLevel>,
+    key_buttons: Res<Input<KeyCode>>,
+) {
+    for (index, key) in LEVEL_KEYS.iter().enumerate().take(BUILT_IN_LEVELS.len()) {
+        if key_buttons.just_pressed(*key) {
+            play(index, &mut current_level, &mut app_state);
+        }
+    }
+    if key_buttons.just_pressed(KeyCode::Escape) {
+        app_state.set(AppState::GameStart);
+    }
+}
+
+fn despawn_level_select(mut commands: Commands, query: Query<Entity, With<LevelSelectMenu>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
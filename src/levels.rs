@@ -0,0 +1,401 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext, LoadState},
+    prelude::*,
+    reflect::TypePath,
+    utils::BoxedFuture,
+};
+use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::coins::{self, Coins};
+use crate::flappy::{self, Crashed};
+use crate::interpolation::Interpolated;
+use crate::lives::{self, Lives};
+use crate::obstacles::Obstacle;
+use crate::pause::PauseState;
+use crate::pipe_behaviours::PipeBehaviour;
+use crate::pipes::{self, PIPE_WIDTH};
+use crate::playfield::Playfield;
+use crate::power_ups::{self, PowerUpKind, PowerUpRegistry, ScrollSpeed, Scrolls};
+use crate::rng::GameRng;
+use crate::AppState;
+use crate::SimulationSet;
+
+/// Level files that ship with the game, in the order the level select screen lists them.
+pub const BUILT_IN_LEVELS: [&str; 2] =
+    ["levels/first_flight.level.ron", "levels/gauntlet.level.ron"];
+
+const FINISH_LINE_WIDTH: f32 = 40.0;
+const FINISH_LINE_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.8);
+
+/// Plays handcrafted levels from `.level.ron` files to a finish line, in place of the
+/// endless pipe generator, whenever [`CurrentLevel`] names one.
+pub struct LevelsPlugin;
+
+impl Plugin for LevelsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Level>()
+            .register_asset_loader(LevelLoader)
+            .init_resource::<CurrentLevel>()
+            .add_systems(Startup, load_built_in_levels)
+            .add_systems(
+                OnEnter(AppState::InGame),
+                start_level.after(lives::reset_lives),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    advance_level,
+                    count_deaths.after(flappy::detect_flappy_lethal_collision),
+                    despawn_passed_pickups,
+                )
+                    .in_set(SimulationSet::Gameplay)
+                    .run_if(resource_exists::<LevelRun>())
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_systems(OnExit(AppState::InGame), end_level);
+    }
+}
+
+/// A handcrafted course: everything in it, where, and how far away the finish line is.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Level {
+    pub name: String,
+    /// How fast the level scrolls past, in pixels per second.
+    pub speed: f32,
+    /// Distance from the start to the finish line.
+    pub length: f32,
+    /// Sorted by `at` once loaded.
+    pub pieces: Vec<LevelPiece>,
+}
+
+/// Something placed `at` pixels from the start and `y` above the middle of the playfield.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct LevelPiece {
+    pub at: f32,
+    pub y: f32,
+    /// How fast this piece moves, if not at the level's speed.
+    #[serde(default)]
+    pub speed: Option<f32>,
+    pub kind: PieceKind,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub enum PieceKind {
+    /// A pipe pair with its gap centred on the piece.
+    Pipes {
+        gap_size: f32,
+        #[serde(default)]
+        behaviour: Option<PipeBehaviour>,
+    },
+    Obstacle(Obstacle),
+    Coin,
+    PowerUp(PowerUpKind),
+}
+
+impl Level {
+    pub fn coin_count(&self) -> u32 {
+        self.pieces
+            .iter()
+            .filter(|piece| matches!(piece.kind, PieceKind::Coin))
+            .count() as u32
+    }
+
+    /// One star for finishing, one for every coin and one for never crashing.
+    pub fn stars(&self, coins: u32, deaths: u32) -> u8 {
+        1 + u8::from(coins >= self.coin_count()) + u8::from(deaths == 0)
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.speed <= 0.0 {
+            problems.push("speed must be positive".to_string());
+        }
+        if self.length <= 0.0 {
+            problems.push("length must be positive".to_string());
+        }
+        for piece in &self.pieces {
+            if piece.speed.is_some_and(|speed| speed <= 0.0) {
+                problems.push(format!("piece at {} needs a positive speed", piece.at));
+            }
+            match &piece.kind {
+                PieceKind::Pipes { gap_size, .. } if *gap_size <= 0.0 => {
+                    problems.push(format!("pipes at {} need a positive gap_size", piece.at));
+                }
+                PieceKind::Obstacle(obstacle) => {
+                    if let Some(problem) = obstacle.problem() {
+                        problems.push(format!("at {}: {problem}", piece.at));
+                    }
+                }
+                _ => {}
+            }
+        }
+        problems
+    }
+}
+
+/// Level file the next run plays, as a path under `assets/`. `None` plays endless.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct CurrentLevel(pub Option<String>);
+
+/// Handles to [`BUILT_IN_LEVELS`], kept so they stay loaded for the level select screen.
+#[derive(Resource)]
+pub struct BuiltInLevels(pub Vec<Handle<Level>>);
+
+/// How the last level run ended, when it reached the finish line.
+#[derive(Resource, Debug, Clone)]
+pub struct LevelResult {
+    pub path: String,
+    pub stars: u8,
+    pub deaths: u32,
+    pub coins: u32,
+    pub coin_count: u32,
+}
+
+/// Progress through the level being played. Only exists during a level run.
+#[derive(Resource)]
+pub struct LevelRun {
+    path: String,
+    handle: Handle<Level>,
+    distance: f32,
+    next_piece: usize,
+    finish_spawned: bool,
+    deaths: u32,
+}
+
+/// A coin or pickup placed by a level rather than carried by a pipe pair.
+#[derive(Component)]
+struct LevelPickup;
+
+#[derive(Component)]
+struct FinishLine;
+
+#[derive(Debug, Error)]
+pub enum LevelLoaderError {
+    #[error("could not read level file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse level file: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("invalid level: {}", .0.join("; "))]
+    Invalid(Vec<String>),
+}
+
+#[derive(Default)]
+struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = Level;
+    type Settings = ();
+    type Error = LevelLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Level, LevelLoaderError>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let mut level: Level = ron::de::from_bytes(&bytes)?;
+            let problems = level.problems();
+            if !problems.is_empty() {
+                return Err(LevelLoaderError::Invalid(problems));
+            }
+            level.pieces.sort_by(|a, b| a.at.total_cmp(&b.at));
+            Ok(level)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+fn load_built_in_levels(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(BuiltInLevels(
+        BUILT_IN_LEVELS
+            .iter()
+            .map(|path| asset_server.load(*path))
+            .collect(),
+    ));
+}
+
+pub fn start_level(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    current: Res<CurrentLevel>,
+    mut lives: ResMut<Lives>,
+) {
+    commands.remove_resource::<LevelResult>();
+    let Some(path) = &current.0 else {
+        return;
+    };
+    commands.insert_resource(LevelRun {
+        path: path.clone(),
+        handle: asset_server.load(path.clone()),
+        distance: 0.0,
+        next_piece: 0,
+        finish_spawned: false,
+        deaths: 0,
+    });
+    // crashing costs stars instead
+    lives.unlimited = true;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn advance_level(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    levels: Res<Assets<Level>>,
+    mut run: ResMut<LevelRun>,
+    time: Res<Time>,
+    playfield: Res<Playfield>,
+    scroll_speed: Res<ScrollSpeed>,
+    registry: Res<PowerUpRegistry>,
+    mut game_rng: ResMut<GameRng>,
+    coins: Res<Coins>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut app_state: ResMut<NextState<AppState>>,
+) {
+    // nothing moves until the file has loaded
+    let Some(level) = levels.get(&run.handle) else {
+        if asset_server.get_load_state(&run.handle) == Some(LoadState::Failed) {
+            error!("could not load level {}, back to the menu", run.path);
+            current_level.0 = None;
+            app_state.set(AppState::GameStart);
+        }
+        return;
+    };
+    run.distance += level.speed * scroll_speed.0 * time.delta_seconds();
+
+    // pieces appear where pipe pairs would, and are placed relative to the bird
+    let spawn_x = playfield.width + PIPE_WIDTH;
+    while let Some(piece) = level.pieces.get(run.next_piece) {
+        let x = piece.at - run.distance;
+        if x > spawn_x {
+            break;
+        }
+        let position = Vec2::new(x, piece.y);
+        let velocity = Vec2::new(-piece.speed.unwrap_or(level.speed) * scroll_speed.0, 0.0);
+        match &piece.kind {
+            PieceKind::Pipes {
+                gap_size,
+                behaviour,
+            } => {
+                let mut pipe_group = pipes::spawn_pipe_pair(
+                    &mut commands,
+                    &asset_server,
+                    position,
+                    velocity,
+                    *gap_size,
+                );
+                if let Some(behaviour) = behaviour {
+                    behaviour.insert(&mut pipe_group, piece.y, *gap_size);
+                }
+            }
+            PieceKind::Obstacle(obstacle) => {
+                obstacle.spawn(
+                    &mut commands,
+                    &asset_server,
+                    position,
+                    velocity,
+                    &mut game_rng.obstacles,
+                );
+            }
+            PieceKind::Coin => {
+                commands
+                    .spawn(coins::coin_bundle(position.extend(0.5)))
+                    .insert(moving_piece(velocity));
+            }
+            PieceKind::PowerUp(kind) => {
+                if let Some(effect) = registry.get(*kind) {
+                    commands
+                        .spawn(power_ups::pickup_bundle(
+                            *kind,
+                            effect,
+                            position.extend(0.5),
+                        ))
+                        .insert(moving_piece(velocity));
+                }
+            }
+        }
+        run.next_piece += 1;
+    }
+
+    let finish_x = level.length - run.distance;
+    if !run.finish_spawned && finish_x <= spawn_x {
+        run.finish_spawned = true;
+        commands
+            .spawn(SpriteBundle {
+                transform: Transform::from_xyz(finish_x, 0.0, 0.5),
+                sprite: Sprite {
+                    color: FINISH_LINE_COLOR,
+                    custom_size: Some(Vec2::new(FINISH_LINE_WIDTH, playfield.height * 2.0)),
+                    ..default()
+                },
+                ..default()
+            })
+            .insert(RigidBody::KinematicVelocityBased)
+            .insert(Velocity::linear(Vec2::new(
+                -level.speed * scroll_speed.0,
+                0.0,
+            )))
+            .insert(Interpolated::default())
+            .insert(Scrolls)
+            .insert(FinishLine);
+    }
+
+    if finish_x <= 0.0 {
+        commands.insert_resource(LevelResult {
+            path: run.path.clone(),
+            stars: level.stars(coins.0, run.deaths),
+            deaths: run.deaths,
+            coins: coins.0,
+            coin_count: level.coin_count(),
+        });
+        app_state.set(AppState::GameOver);
+    }
+}
+
+fn moving_piece(velocity: Vec2) -> impl Bundle {
+    (
+        RigidBody::KinematicVelocityBased,
+        Velocity::linear(velocity),
+        Interpolated::default(),
+        Scrolls,
+        LevelPickup,
+    )
+}
+
+fn count_deaths(mut crashes: EventReader<Crashed>, mut run: ResMut<LevelRun>) {
+    run.deaths += crashes.read().count() as u32;
+}
+
+fn despawn_passed_pickups(
+    mut commands: Commands,
+    playfield: Res<Playfield>,
+    query: Query<(Entity, &Transform), With<LevelPickup>>,
+) {
+    for (entity, transform) in query.iter() {
+        if transform.translation.x < -(playfield.width + PIPE_WIDTH) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn end_level(
+    mut commands: Commands,
+    q_pickups: Query<Entity, With<LevelPickup>>,
+    q_finish_line: Query<Entity, With<FinishLine>>,
+) {
+    commands.remove_resource::<LevelRun>();
+    for entity in q_pickups.iter().chain(q_finish_line.iter()) {
+        commands.entity(entity).despawn_recursive();
+    }
+}
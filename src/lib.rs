@@ -0,0 +1,188 @@
+use bevy::{prelude::*, time::Stopwatch};
+use bevy_rapier2d::prelude::*;
+
+pub mod autopilot;
+pub mod background;
+pub mod bounds;
+pub mod character_select;
+pub mod characters;
+pub mod cli;
+pub mod coins;
+pub mod difficulty;
+pub mod env;
+pub mod flappy;
+pub mod game_modes;
+pub mod game_over;
+pub mod ghost;
+pub mod headless;
+pub mod in_game_ui;
+pub mod interpolation;
+pub mod level_select;
+pub mod levels;
+pub mod lives;
+pub mod main_menu;
+pub mod obstacles;
+pub mod pause;
+pub mod pipe_behaviours;
+pub mod pipes;
+pub mod players;
+pub mod playfield;
+pub mod power_ups;
+pub mod replay;
+pub mod rng;
+pub mod save;
+pub mod training;
+pub mod tuning;
+pub mod widgets;
+
+#[cfg(test)]
+mod tests;
+
+use autopilot::AutopilotPlugin;
+use background::BackgroundPlugin;
+use bounds::BoundsPlugin;
+use characters::SelectedCharacter;
+use coins::CoinsPlugin;
+use difficulty::DifficultyPlugin;
+use flappy::FlappyPlugin;
+use game_modes::GameModesPlugin;
+use levels::LevelsPlugin;
+use lives::LivesPlugin;
+use obstacles::ObstaclesPlugin;
+use pause::{PausePlugin, PauseState};
+use pipe_behaviours::PipeBehavioursPlugin;
+use pipes::PipesPlugin;
+use players::PlayersPlugin;
+use playfield::PlayfieldPlugin;
+use power_ups::PowerUpsPlugin;
+use replay::ReplayPlugin;
+use rng::RngPlugin;
+use tuning::TuningPlugin;
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
+pub enum AppState {
+    #[default]
+    GameStart,
+    /// Picking a level to play instead of an endless run.
+    LevelSelect,
+    /// Picking a bird between the main menu and a run.
+    CharacterSelect,
+    InGame,
+    GameOver,
+    /// Passed through for a frame so restarting a run exits and re-enters `InGame`.
+    Restarting,
+}
+
+/// Rate the simulation and the physics step run at, independent of the frame rate.
+const SIMULATION_HZ: f64 = 60.0;
+
+/// Order of the simulation systems within each [`FixedUpdate`] step.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SimulationSet {
+    /// Advances [`SimulationTick`].
+    Tick,
+    /// Sends the step's [`flappy::Flap`]s, from player input and anything else flying a bird.
+    Input,
+    /// Ticks power-up timers and applies their effects, so the rest of the step sees them.
+    PowerUps,
+    /// Everything else, ahead of the physics step.
+    Gameplay,
+}
+
+/// Gameplay shared by the windowed and the headless app.
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<AppState>()
+            .insert_resource(Score(0))
+            .init_resource::<RunTime>()
+            .init_resource::<SimulationTick>()
+            .init_resource::<SelectedCharacter>()
+            .insert_resource(Time::<Fixed>::from_hz(SIMULATION_HZ))
+            .insert_resource(RapierConfiguration {
+                timestep_mode: TimestepMode::Fixed {
+                    dt: 1.0 / SIMULATION_HZ as f32,
+                    substeps: 1,
+                },
+                ..default()
+            })
+            .add_plugins(
+                RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0).in_fixed_schedule(),
+            )
+            .configure_sets(
+                FixedUpdate,
+                (
+                    SimulationSet::Tick,
+                    SimulationSet::Input,
+                    SimulationSet::PowerUps,
+                    SimulationSet::Gameplay,
+                )
+                    .chain()
+                    .before(PhysicsSet::SyncBackend),
+            )
+            .add_plugins(PlayfieldPlugin)
+            .add_plugins(RngPlugin)
+            .add_plugins(PausePlugin)
+            .add_plugins(TuningPlugin)
+            .add_plugins(DifficultyPlugin)
+            .add_plugins(GameModesPlugin)
+            .add_plugins(BackgroundPlugin)
+            .add_plugins(FlappyPlugin)
+            .add_plugins(BoundsPlugin)
+            .add_plugins(LivesPlugin)
+            .add_plugins(PlayersPlugin)
+            .add_plugins(PipesPlugin)
+            .add_plugins(PipeBehavioursPlugin)
+            .add_plugins(ObstaclesPlugin)
+            .add_plugins(CoinsPlugin)
+            .add_plugins(PowerUpsPlugin)
+            .add_plugins(LevelsPlugin)
+            .add_plugins(ReplayPlugin)
+            .add_plugins(AutopilotPlugin)
+            .add_systems(OnEnter(AppState::InGame), start_run)
+            .add_systems(OnEnter(AppState::Restarting), restart_run)
+            .add_systems(
+                FixedUpdate,
+                (
+                    advance_tick.in_set(SimulationSet::Tick),
+                    tick_run_time.in_set(SimulationSet::Gameplay),
+                )
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct Score(u32);
+
+/// How long the current run has lasted.
+#[derive(Resource, Default)]
+pub struct RunTime(Stopwatch);
+
+/// Fixed-timestep ticks simulated in the current run. Replays are keyed by it.
+#[derive(Resource, Default)]
+pub struct SimulationTick(pub u64);
+
+fn start_run(
+    mut score: ResMut<Score>,
+    mut run_time: ResMut<RunTime>,
+    mut tick: ResMut<SimulationTick>,
+) {
+    score.0 = 0;
+    run_time.0.reset();
+    tick.0 = 0;
+}
+
+fn advance_tick(mut tick: ResMut<SimulationTick>) {
+    tick.0 += 1;
+}
+
+fn restart_run(mut app_state: ResMut<NextState<AppState>>) {
+    app_state.set(AppState::InGame);
+}
+
+fn tick_run_time(time: Res<Time>, mut run_time: ResMut<RunTime>) {
+    run_time.0.tick(time.delta());
+}
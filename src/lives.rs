@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::flappy::{self, Crashed, Flappy, Invulnerable};
+use crate::pause::PauseState;
+use crate::players::{PlayerCount, Scoreboard};
+use crate::tuning::Tuning;
+use crate::AppState;
+use crate::SimulationSet;
+
+/// Ends the run once the bird has crashed as many times as it has lives, respawning
+/// it in between. In a multiplayer run a crash takes the bird out instead.
+pub struct LivesPlugin;
+
+impl Plugin for LivesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Lives>()
+            .add_systems(OnEnter(AppState::InGame), reset_lives)
+            .add_systems(
+                FixedUpdate,
+                lose_life
+                    .after(flappy::detect_flappy_lethal_collision)
+                    .in_set(SimulationSet::Gameplay)
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+/// Crashes the bird can still survive, plus one. A single life is the classic game.
+#[derive(Resource, Debug, Default)]
+pub struct Lives {
+    pub remaining: u32,
+    /// Crashes still respawn the bird but never cost a life.
+    pub unlimited: bool,
+}
+
+pub fn reset_lives(tuning: Res<Tuning>, mut lives: ResMut<Lives>) {
+    lives.remaining = tuning.lives.count;
+    lives.unlimited = false;
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn lose_life(
+    mut commands: Commands,
+    mut crashes: EventReader<Crashed>,
+    mut lives: ResMut<Lives>,
+    mut app_state: ResMut<NextState<AppState>>,
+    mut q_flappy: Query<(&Flappy, &mut Transform, &mut Velocity)>,
+    players: Res<PlayerCount>,
+    mut scoreboard: ResMut<Scoreboard>,
+    tuning: Res<Tuning>,
+) {
+    for Crashed(entity) in crashes.read() {
+        // each bird of a multiplayer run has a single life, unless lives are unlimited
+        if players.0 > 1 && !lives.unlimited {
+            if let Ok((flappy, _, _)) = q_flappy.get(*entity) {
+                scoreboard.eliminate(flappy.0);
+            }
+            commands.entity(*entity).despawn_recursive();
+            continue;
+        }
+        if !lives.unlimited {
+            lives.remaining = lives.remaining.saturating_sub(1);
+        }
+        if lives.remaining == 0 {
+            app_state.set(AppState::GameOver);
+            continue;
+        }
+
+        // back to the centre, with time to get clear of whatever it hit
+        if let Ok((_, mut transform, mut velocity)) = q_flappy.get_mut(*entity) {
+            transform.translation.y = 0.0;
+            transform.rotation = Quat::IDENTITY;
+            *velocity = Velocity::zero();
+        }
+        commands
+            .entity(*entity)
+            .insert(Invulnerable::bundle(tuning.lives.invulnerability_secs));
+    }
+}
@@ -1,65 +1,121 @@
 use bevy::prelude::*;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
-use bevy_rapier2d::prelude::*;
 
-mod background;
-mod flappy;
-mod in_game_ui;
-mod main_menu;
-mod pipes;
+use bevy_flappy::autopilot::Autopilot;
+use bevy_flappy::character_select::CharacterSelectPlugin;
+use bevy_flappy::characters::SelectedCharacter;
+use bevy_flappy::cli::Args;
+use bevy_flappy::game_over::GameOverPlugin;
+use bevy_flappy::ghost::GhostPlugin;
+use bevy_flappy::headless;
+use bevy_flappy::in_game_ui::InGameUiPlugin;
+use bevy_flappy::interpolation::InterpolationPlugin;
+use bevy_flappy::level_select::LevelSelectPlugin;
+use bevy_flappy::levels::CurrentLevel;
+use bevy_flappy::main_menu::MainMenuPlugin;
+use bevy_flappy::pause::PauseMenuPlugin;
+use bevy_flappy::playfield::Playfield;
+use bevy_flappy::replay::{Replay, ReplayPlayback, ReplayRecorder};
+use bevy_flappy::rng::RngSeed;
+use bevy_flappy::save::SavePlugin;
+use bevy_flappy::training::Genome;
+use bevy_flappy::widgets::WidgetsPlugin;
+use bevy_flappy::GamePlugin;
 
-use background::BackgroundPlugin;
-use flappy::FlappyPlugin;
-use in_game_ui::InGameUiPlugin;
-use main_menu::MainMenuPlugin;
-use pipes::PipesPlugin;
+fn main() {
+    let args = Args::parse();
+
+    let playback = args.replay.as_ref().map(|path| {
+        Replay::read(path).unwrap_or_else(|error| {
+            eprintln!("error: could not load replay {}: {error}", path.display());
+            std::process::exit(2);
+        })
+    });
+
+    let genome = args.genome.as_ref().map(|path| {
+        Genome::read(path).unwrap_or_else(|error| {
+            eprintln!("error: could not load genome {}: {error}", path.display());
+            std::process::exit(2);
+        })
+    });
 
-#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
-pub enum AppState {
-    #[default]
-    GameStart,
-    InGame,
-    GameOver,
+    let mut app = if args.headless {
+        let mut app = headless::app(Playfield::default());
+        // a replay starts its run once the tuning it was recorded with has loaded
+        if playback.is_none() {
+            app.add_systems(Startup, headless::start_run);
+        }
+        app
+    } else {
+        windowed_app()
+    };
+    let seed = playback.as_ref().map(|replay| replay.seed).or(args.seed);
+    app.insert_resource(RngSeed(seed));
+    if let Some(character) = args.character {
+        app.insert_resource(SelectedCharacter(character));
+    }
+    if let Some(mode) = args.mode {
+        app.insert_resource(mode);
+    }
+    if let Some(players) = args.players {
+        app.insert_resource(players);
+    }
+    if let Some(level) = args.level {
+        app.insert_resource(CurrentLevel(Some(level)));
+    }
+    if let Some(replay) = playback {
+        app.insert_resource(ReplayPlayback::new(replay));
+    }
+    if let Some(genome) = genome {
+        app.insert_resource(Autopilot::with_genome(genome));
+    } else if args.autopilot {
+        app.insert_resource(Autopilot::default());
+    }
+    if let Some(path) = args.record {
+        app.insert_resource(ReplayRecorder::new(path));
+    }
+    app.run();
 }
 
-fn main() {
-    App::new()
-        .insert_resource(ClearColor(Color::rgb(
-            208.0 / 255.0,
-            244.0 / 255.0,
-            247.0 / 255.0,
-        )))
-        .add_state::<AppState>()
-        .add_plugins(
-            DefaultPlugins
-                .set(WindowPlugin {
-                    primary_window: Some(Window {
-                        title: "Bevy Flappy".into(),
-                        resolution: (1200.0, 600.0).into(),
-                        ..default()
-                    }),
+fn windowed_app() -> App {
+    let playfield = Playfield::default();
+    let mut app = App::new();
+    app.insert_resource(ClearColor(Color::rgb(
+        208.0 / 255.0,
+        244.0 / 255.0,
+        247.0 / 255.0,
+    )))
+    .add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: Some(Window {
+                    title: "Bevy Flappy".into(),
+                    resolution: (playfield.width, playfield.height).into(),
                     ..default()
-                })
-                .set(ImagePlugin::default_nearest()),
-        )
-        // .add_plugins(WorldInspectorPlugin::new())
-        .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0))
-        // .add_plugins(RapierDebugRenderPlugin::default())
-        .add_plugins(BackgroundPlugin)
-        .add_plugins(MainMenuPlugin)
-        .add_plugins(InGameUiPlugin)
-        .add_plugins(FlappyPlugin)
-        .add_plugins(PipesPlugin)
-        .add_systems(Startup, setup)
-        .run();
+                }),
+                ..default()
+            })
+            .set(ImagePlugin::default_nearest()),
+    )
+    // .add_plugins(WorldInspectorPlugin::new())
+    // .add_plugins(RapierDebugRenderPlugin::default())
+    .add_plugins(GamePlugin)
+    .add_plugins(InterpolationPlugin)
+    .add_plugins(MainMenuPlugin)
+    .add_plugins(LevelSelectPlugin)
+    .add_plugins(CharacterSelectPlugin)
+    .add_plugins(InGameUiPlugin)
+    .add_plugins(GameOverPlugin)
+    .add_plugins(SavePlugin)
+    .add_plugins(GhostPlugin)
+    .add_plugins(PauseMenuPlugin)
+    .add_plugins(WidgetsPlugin)
+    .add_systems(Startup, setup);
+    app
 }
 
-#[derive(Resource)]
-pub struct Score(u32);
-
 fn setup(mut commands: Commands) {
     let mut camera = Camera2dBundle::default();
     camera.projection.scale = 2.0;
     commands.spawn(camera);
-    commands.insert_resource(Score(0));
 }
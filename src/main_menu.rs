@@ -1,5 +1,9 @@
 use bevy::prelude::*;
 
+use crate::game_modes::GameMode;
+use crate::levels::CurrentLevel;
+use crate::players::{PlayerCount, PLAYER_CONTROLS};
+use crate::save::PlayerStats;
 use crate::AppState;
 
 pub struct MainMenuPlugin;
@@ -9,7 +13,13 @@ impl Plugin for MainMenuPlugin {
         app.add_systems(OnEnter(AppState::GameStart), spawn_main_menu)
             .add_systems(
                 Update,
-                wait_for_interaction_to_start.run_if(in_state(AppState::GameStart)),
+                (
+                    wait_for_interaction_to_start,
+                    show_game_mode,
+                    show_player_count,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::GameStart)),
             )
             .add_systems(OnExit(AppState::GameStart), despawn_main_menu);
     }
@@ -18,20 +28,103 @@ impl Plugin for MainMenuPlugin {
 #[derive(Component)]
 pub struct MainMenu;
 
+#[derive(Component)]
+struct GameModeText;
+
+#[derive(Component)]
+struct StatsText;
+
+#[derive(Component)]
+struct PlayersText;
+
 fn wait_for_interaction_to_start(
     mut app_state: ResMut<NextState<AppState>>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut mode: ResMut<GameMode>,
+    mut players: ResMut<PlayerCount>,
     mouse_buttons: Res<Input<MouseButton>>,
     key_buttons: Res<Input<KeyCode>>,
 ) {
-    if mouse_buttons.any_just_pressed([MouseButton::Left, MouseButton::Right]) {
-        app_state.set(AppState::InGame);
+    if mouse_buttons.any_just_pressed([MouseButton::Left, MouseButton::Right])
+        || key_buttons.just_pressed(KeyCode::Space)
+    {
+        current_level.0 = None;
+        app_state.set(AppState::CharacterSelect);
+    }
+    if key_buttons.just_pressed(KeyCode::L) {
+        app_state.set(AppState::LevelSelect);
+    }
+    if key_buttons.just_pressed(KeyCode::M) {
+        *mode = mode.next();
+    }
+    if key_buttons.just_pressed(KeyCode::P) {
+        *players = players.next();
+    }
+}
+
+fn show_game_mode(
+    mode: Res<GameMode>,
+    stats: Res<PlayerStats>,
+    mut q_mode: Query<&mut Text, (With<GameModeText>, Without<StatsText>)>,
+    mut q_stats: Query<&mut Text, With<StatsText>>,
+) {
+    if !mode.is_changed() {
+        return;
     }
-    if key_buttons.just_pressed(KeyCode::Space) {
-        app_state.set(AppState::InGame);
+    for mut text in q_mode.iter_mut() {
+        text.sections[0].value = describe_mode(*mode);
+    }
+    for mut text in q_stats.iter_mut() {
+        text.sections[0].value = describe_stats(*mode, &stats);
     }
 }
 
-fn spawn_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn show_player_count(players: Res<PlayerCount>, mut q_text: Query<&mut Text, With<PlayersText>>) {
+    if !players.is_changed() {
+        return;
+    }
+    for mut text in q_text.iter_mut() {
+        text.sections[0].value = describe_players(*players);
+    }
+}
+
+fn describe_players(players: PlayerCount) -> String {
+    if players.0 == 1 {
+        return "Players: 1  (P to change)".to_string();
+    }
+    let controls: Vec<String> = PLAYER_CONTROLS[..players.0]
+        .iter()
+        .map(|control| control.label())
+        .collect();
+    format!(
+        "Players: {}  (P to change)  Flap with {}",
+        players.0,
+        controls.join(", ")
+    )
+}
+
+fn describe_mode(mode: GameMode) -> String {
+    format!("Mode: {}  (M to change)", mode.name())
+}
+
+fn describe_stats(mode: GameMode, stats: &PlayerStats) -> String {
+    format!(
+        "Best: {}  Runs: {}  Pipes: {}  Played: {}  Coins: {}",
+        stats.best_score(mode),
+        stats.total_runs,
+        stats.total_pipes_passed,
+        format_play_time(stats.total_play_time_secs),
+        stats.coins,
+    )
+}
+
+fn spawn_main_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    stats: Res<PlayerStats>,
+    mode: Res<GameMode>,
+    players: Res<PlayerCount>,
+) {
     commands
         .spawn(NodeBundle {
             style: Style {
@@ -49,7 +142,7 @@ fn spawn_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
         .with_children(|parent| {
             parent.spawn(
                 TextBundle::from_section(
-                    "Click or press space to start",
+                    "Click or press space to start, L for levels",
                     TextStyle {
                         font: asset_server.load("fonts/Monocraft.otf"),
                         font_size: 35.0,
@@ -75,9 +168,64 @@ fn spawn_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                     ..default()
                 }),
             );
+            parent
+                .spawn(
+                    TextBundle::from_section(
+                        describe_mode(*mode),
+                        TextStyle {
+                            font: asset_server.load("fonts/Monocraft.otf"),
+                            font_size: 25.0,
+                            color: Color::rgb(0.086, 0.086, 0.086),
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect::all(Val::Px(5.0)),
+                        ..default()
+                    }),
+                )
+                .insert(GameModeText);
+            parent
+                .spawn(
+                    TextBundle::from_section(
+                        describe_players(*players),
+                        TextStyle {
+                            font: asset_server.load("fonts/Monocraft.otf"),
+                            font_size: 25.0,
+                            color: Color::rgb(0.086, 0.086, 0.086),
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect::all(Val::Px(5.0)),
+                        ..default()
+                    }),
+                )
+                .insert(PlayersText);
+            if stats.total_runs > 0 {
+                parent
+                    .spawn(
+                        TextBundle::from_section(
+                            describe_stats(*mode, &stats),
+                            TextStyle {
+                                font: asset_server.load("fonts/Monocraft.otf"),
+                                font_size: 20.0,
+                                color: Color::rgb(0.086, 0.086, 0.086),
+                            },
+                        )
+                        .with_style(Style {
+                            margin: UiRect::all(Val::Px(5.0)),
+                            ..default()
+                        }),
+                    )
+                    .insert(StatsText);
+            }
         });
 }
 
+fn format_play_time(secs: f64) -> String {
+    let secs = secs as u64;
+    format!("{}h {:02}m {:02}s", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
 fn despawn_main_menu(mut commands: Commands, query: Query<Entity, With<MainMenu>>) {
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
@@ -0,0 +1,326 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
+use serde::Deserialize;
+
+use crate::flappy::{Bird, Flappy, Lethal};
+use crate::interpolation::Interpolated;
+use crate::pause::PauseState;
+use crate::pipe_behaviours::PipeBehaviour;
+use crate::pipes::{self, PipeDespawnArea, MIN_GAP_SIZE, PIPE_WIDTH};
+use crate::players::Scoreboard;
+use crate::power_ups::{ScoreMultiplier, Scrolls};
+use crate::AppState;
+use crate::Score;
+use crate::SimulationSet;
+
+const BAR_THICKNESS: f32 = 40.0;
+const BAR_COLOR: Color = Color::rgb(0.45, 0.3, 0.2);
+const SPIKE_BALL_COLOR: Color = Color::rgb(0.25, 0.25, 0.3);
+const ENEMY_BIRD_COLOR: Color = Color::rgb(0.8, 0.1, 0.1);
+
+/// Hazards other than plain pipe pairs. They take turns with the pipe pairs in
+/// [`pipes::spawn_pipe`], and kill the bird through [`Lethal`] like pipes do.
+pub struct ObstaclesPlugin;
+
+impl Plugin for ObstaclesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ObstaclePassed>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    steer_enemy_birds,
+                    score_passed_obstacles,
+                    despawn_passed_obstacles,
+                )
+                    .in_set(SimulationSet::Gameplay)
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_systems(OnExit(AppState::InGame), despawn);
+    }
+}
+
+/// The birds flew past an obstacle that isn't a pipe pair.
+#[derive(Event)]
+pub struct ObstaclePassed;
+
+/// An obstacle as configured in the tuning file, spawned in place of a pipe pair.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub enum Obstacle {
+    /// A bar `length` long spinning about its middle at `angular_speed` radians per second.
+    RotatingBar { length: f32, angular_speed: f32 },
+    /// A ball `diameter` across that bobs `amplitude` up and down, once every `period` seconds.
+    SpikeBall {
+        diameter: f32,
+        amplitude: f32,
+        period: f32,
+    },
+    /// A bird `size` across flying at the player `speed` faster than the pipes, turning
+    /// towards it at up to `homing` pixels per second.
+    EnemyBird { size: f32, speed: f32, homing: f32 },
+    /// `pipes` pipe pairs back to back with gaps of `gap_size`, each one `step` higher or
+    /// lower than the last. Each pair scores like any other.
+    Tunnel {
+        pipes: u32,
+        gap_size: f32,
+        step: f32,
+    },
+}
+
+impl Obstacle {
+    /// How far above or below its spawn height the obstacle reaches, which spawning keeps
+    /// inside the playfield.
+    pub fn vertical_reach(&self) -> f32 {
+        match *self {
+            Self::RotatingBar { length, .. } => length / 2.0,
+            Self::SpikeBall {
+                diameter,
+                amplitude,
+                ..
+            } => diameter / 2.0 + amplitude,
+            Self::EnemyBird { size, .. } => size / 2.0,
+            Self::Tunnel {
+                pipes,
+                gap_size,
+                step,
+            } => gap_size.max(MIN_GAP_SIZE) / 2.0 + step * pipes.saturating_sub(1) as f32,
+        }
+    }
+
+    /// Describes what makes the obstacle unusable, if anything.
+    pub fn problem(&self) -> Option<&'static str> {
+        let ok = match *self {
+            Self::RotatingBar { length, .. } => length > 0.0,
+            Self::SpikeBall {
+                diameter,
+                amplitude,
+                period,
+            } => diameter > 0.0 && amplitude >= 0.0 && period > 0.0,
+            Self::EnemyBird {
+                size,
+                speed,
+                homing,
+            } => size > 0.0 && speed >= 0.0 && homing >= 0.0,
+            Self::Tunnel {
+                pipes,
+                gap_size,
+                step,
+            } => pipes > 0 && gap_size > 0.0 && step >= 0.0,
+        };
+        (!ok).then_some(
+            "obstacles need positive sizes, periods and pipe counts and no negative speeds",
+        )
+    }
+
+    /// Spawns the obstacle centred on `position`, scrolling at `velocity` like a pipe pair would.
+    pub fn spawn(
+        &self,
+        commands: &mut Commands,
+        asset_server: &AssetServer,
+        position: Vec2,
+        velocity: Vec2,
+        rng: &mut ChaCha8Rng,
+    ) {
+        match *self {
+            Self::RotatingBar {
+                length,
+                angular_speed,
+            } => {
+                let direction = if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+                spawn_hazard(
+                    commands,
+                    position,
+                    Sprite {
+                        color: BAR_COLOR,
+                        custom_size: Some(Vec2::new(length, BAR_THICKNESS)),
+                        ..default()
+                    },
+                    default(),
+                    Collider::cuboid(length / 2.0, BAR_THICKNESS / 2.0),
+                )
+                .insert(Velocity {
+                    linvel: velocity,
+                    angvel: angular_speed * direction,
+                });
+            }
+            Self::SpikeBall {
+                diameter,
+                amplitude,
+                period,
+            } => {
+                let mut ball = spawn_hazard(
+                    commands,
+                    position,
+                    Sprite {
+                        color: SPIKE_BALL_COLOR,
+                        custom_size: Some(Vec2::splat(diameter)),
+                        ..default()
+                    },
+                    default(),
+                    Collider::ball(diameter / 2.0),
+                );
+                ball.insert(Velocity::linear(velocity))
+                    .insert(LockedAxes::ROTATION_LOCKED);
+                PipeBehaviour::Oscillate { amplitude, period }.insert(&mut ball, position.y, 0.0);
+            }
+            Self::EnemyBird {
+                size,
+                speed,
+                homing,
+            } => {
+                spawn_hazard(
+                    commands,
+                    position,
+                    Sprite {
+                        color: ENEMY_BIRD_COLOR,
+                        custom_size: Some(Vec2::splat(size)),
+                        flip_x: true,
+                        ..default()
+                    },
+                    asset_server.load("bevy.png"),
+                    Collider::ball(size / 2.0),
+                )
+                .insert(Velocity::linear(velocity - Vec2::new(speed, 0.0)))
+                .insert(LockedAxes::ROTATION_LOCKED)
+                .insert(EnemyBird { homing });
+            }
+            Self::Tunnel {
+                pipes,
+                gap_size,
+                step,
+            } => {
+                let gap_size = gap_size.max(MIN_GAP_SIZE);
+                let step = if rng.gen_bool(0.5) { step } else { -step };
+                for index in 0..pipes {
+                    let offset = Vec2::new(PIPE_WIDTH, step) * index as f32;
+                    pipes::spawn_pipe_pair(
+                        commands,
+                        asset_server,
+                        position + offset,
+                        velocity,
+                        gap_size,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// The root of an obstacle that isn't made of pipe pairs. Scores once the bird is past it.
+#[derive(Component)]
+pub struct ObstacleBody {
+    counted: bool,
+}
+
+/// Flies at the bird, adjusting its height to meet it.
+#[derive(Component)]
+pub struct EnemyBird {
+    pub homing: f32,
+}
+
+// a body of its own in the pipes' group, so invulnerability and the despawn area treat
+// it like a pipe
+fn spawn_hazard<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    position: Vec2,
+    sprite: Sprite,
+    texture: Handle<Image>,
+    collider: Collider,
+) -> EntityCommands<'w, 's, 'a> {
+    let mut hazard = commands.spawn(SpriteBundle {
+        transform: Transform::from_translation(position.extend(1.0)),
+        sprite,
+        texture,
+        ..default()
+    });
+    hazard
+        .insert(RigidBody::KinematicVelocityBased)
+        .insert(collider)
+        .insert(CollisionGroups::new(
+            Group::from_bits(0b0110).unwrap(),
+            Group::from_bits(0b0110).unwrap(),
+        ))
+        .insert(Interpolated::default())
+        .insert(Scrolls)
+        .insert(Lethal)
+        .insert(ObstacleBody { counted: false });
+    hazard
+}
+
+fn steer_enemy_birds(
+    time: Res<Time>,
+    mut query: Query<(&EnemyBird, &Transform, &mut Velocity)>,
+    q_flappy: Query<&Transform, With<Bird>>,
+) {
+    let delta = time.delta_seconds();
+
+    for (enemy, transform, mut velocity) in query.iter_mut() {
+        // it goes after whichever bird is nearest its height
+        let Some(flappy) = q_flappy.iter().min_by(|a, b| {
+            let a = (a.translation.y - transform.translation.y).abs();
+            let b = (b.translation.y - transform.translation.y).abs();
+            a.total_cmp(&b)
+        }) else {
+            return;
+        };
+        let distance = flappy.translation.y - transform.translation.y;
+        // once past the bird it keeps its height
+        velocity.linvel.y = if transform.translation.x > flappy.translation.x && delta > 0.0 {
+            (distance / delta).clamp(-enemy.homing, enemy.homing)
+        } else {
+            0.0
+        };
+    }
+}
+
+pub fn score_passed_obstacles(
+    mut query: Query<(&Transform, &mut ObstacleBody)>,
+    q_bird: Query<&Transform, With<Bird>>,
+    q_flappy: Query<&Flappy>,
+    mut score: ResMut<Score>,
+    mut scoreboard: ResMut<Scoreboard>,
+    score_multiplier: Res<ScoreMultiplier>,
+    mut passed: EventWriter<ObstaclePassed>,
+) {
+    // every bird flies at the same x, so they all pass an obstacle together
+    let Some(bird) = q_bird.iter().next() else {
+        return;
+    };
+    for (transform, mut obstacle) in query.iter_mut() {
+        if !obstacle.counted && transform.translation.x < bird.translation.x {
+            obstacle.counted = true;
+            passed.send(ObstaclePassed);
+            for player in q_flappy.iter() {
+                let player_score = scoreboard.award(player.0, score_multiplier.0);
+                score.0 = score.0.max(player_score);
+            }
+        }
+    }
+}
+
+fn despawn_passed_obstacles(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    query_despawn_area: Query<Entity, With<PipeDespawnArea>>,
+    query: Query<Entity, With<ObstacleBody>>,
+) {
+    for entity_despawn_area in query_despawn_area.iter() {
+        for entity in query.iter() {
+            if rapier_context
+                .intersection_pair(entity_despawn_area, entity)
+                .is_some()
+            {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+fn despawn(mut commands: Commands, query: Query<Entity, With<ObstacleBody>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
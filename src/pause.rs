@@ -0,0 +1,149 @@
+use bevy::{prelude::*, window::WindowFocused};
+use bevy_rapier2d::prelude::*;
+
+use crate::widgets::spawn_button;
+use crate::AppState;
+
+/// Whether the current run is being simulated. Only meaningful while in [`AppState::InGame`].
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
+
+/// Freezes and resumes the simulation. Shared by the windowed and the headless app.
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<PauseState>()
+            .add_systems(
+                Update,
+                (toggle_pause, pause_on_focus_lost).run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnEnter(PauseState::Paused), freeze_physics)
+            .add_systems(OnExit(PauseState::Paused), resume_physics)
+            .add_systems(OnExit(AppState::InGame), resume);
+    }
+}
+
+/// The overlay shown while paused.
+pub struct PauseMenuPlugin;
+
+impl Plugin for PauseMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(PauseState::Paused), spawn_pause_menu)
+            .add_systems(
+                Update,
+                pause_menu_buttons.run_if(in_state(PauseState::Paused)),
+            )
+            .add_systems(OnExit(PauseState::Paused), despawn_pause_menu);
+    }
+}
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component, Clone, Copy)]
+enum PauseAction {
+    Resume,
+    Restart,
+    Quit,
+}
+
+fn toggle_pause(
+    pause_state: Res<State<PauseState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+    key_buttons: Res<Input<KeyCode>>,
+) {
+    if key_buttons.just_pressed(KeyCode::Escape) {
+        next_pause_state.set(match pause_state.get() {
+            PauseState::Running => PauseState::Paused,
+            PauseState::Paused => PauseState::Running,
+        });
+    }
+}
+
+fn pause_on_focus_lost(
+    mut focus_events: EventReader<WindowFocused>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    if focus_events.read().any(|event| !event.focused) {
+        next_pause_state.set(PauseState::Paused);
+    }
+}
+
+fn freeze_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = false;
+}
+
+fn resume_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = true;
+}
+
+// a run that ends while paused must not leave the next one frozen
+fn resume(mut next_pause_state: ResMut<NextState<PauseState>>) {
+    next_pause_state.set(PauseState::Running);
+}
+
+fn spawn_pause_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("fonts/Monocraft.otf");
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: Color::rgba(1.0, 1.0, 1.0, 0.5).into(),
+            z_index: ZIndex::Global(1),
+            ..default()
+        })
+        .insert(PauseMenu)
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    "Paused",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 50.0,
+                        color: Color::hsl(153.0, 0.67, 0.28),
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(5.0)),
+                    ..default()
+                }),
+            );
+            spawn_button(parent, &font, "Resume", PauseAction::Resume);
+            spawn_button(parent, &font, "Restart", PauseAction::Restart);
+            spawn_button(parent, &font, "Quit", PauseAction::Quit);
+        });
+}
+
+fn pause_menu_buttons(
+    mut app_state: ResMut<NextState<AppState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+    q_buttons: Query<(&Interaction, &PauseAction), Changed<Interaction>>,
+) {
+    for (interaction, action) in q_buttons.iter() {
+        if *interaction == Interaction::Pressed {
+            match action {
+                PauseAction::Resume => next_pause_state.set(PauseState::Running),
+                PauseAction::Restart => app_state.set(AppState::Restarting),
+                PauseAction::Quit => app_state.set(AppState::GameStart),
+            }
+        }
+    }
+}
+
+fn despawn_pause_menu(mut commands: Commands, query: Query<Entity, With<PauseMenu>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
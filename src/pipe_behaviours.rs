@@ -0,0 +1,164 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
+use std::f32::consts::TAU;
+
+use crate::flappy::Bird;
+use crate::pause::PauseState;
+use crate::pipes::{self, PipeGroup, MIN_GAP_SIZE};
+use crate::AppState;
+use crate::SimulationSet;
+
+/// Movement a pipe pair can have on top of scrolling left.
+pub struct PipeBehavioursPlugin;
+
+impl Plugin for PipeBehavioursPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                oscillate,
+                open_and_close.before(pipes::fit_pipes_to_gap),
+                shift_on_approach,
+            )
+                .in_set(SimulationSet::Gameplay)
+                .run_if(in_state(AppState::InGame))
+                .run_if(in_state(PauseState::Running)),
+        );
+    }
+}
+
+/// A behaviour as configured in the tuning file, turned into its component at spawn.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub enum PipeBehaviour {
+    /// The gap drifts up and down `amplitude` around where it spawned, once every `period` seconds.
+    Oscillate { amplitude: f32, period: f32 },
+    /// The gap narrows by up to `closes_by` and opens again, once every `period` seconds. It
+    /// never closes past [`MIN_GAP_SIZE`].
+    OpenClose { closes_by: f32, period: f32 },
+    /// The pair moves `offset` towards the middle of the playfield at `speed` once the bird is
+    /// within `distance` of it.
+    ShiftOnApproach {
+        offset: f32,
+        distance: f32,
+        speed: f32,
+    },
+}
+
+impl PipeBehaviour {
+    /// How far above or below its spawn height the gap may move, which spawning keeps clear.
+    pub fn vertical_reach(&self) -> f32 {
+        match self {
+            Self::Oscillate { amplitude, .. } => *amplitude,
+            Self::OpenClose { .. } | Self::ShiftOnApproach { .. } => 0.0,
+        }
+    }
+
+    /// Adds the behaviour's component to a pipe pair spawned at `y` with a gap of `gap_size`.
+    pub fn insert(&self, pipe_group: &mut EntityCommands, y: f32, gap_size: f32) {
+        match *self {
+            Self::Oscillate { amplitude, period } => {
+                pipe_group.insert(Oscillate {
+                    amplitude,
+                    period,
+                    elapsed: 0.0,
+                });
+            }
+            Self::OpenClose { closes_by, period } => {
+                pipe_group.insert(OpenClose {
+                    widest: gap_size,
+                    narrowest: f32::max(gap_size - closes_by, MIN_GAP_SIZE),
+                    period,
+                    elapsed: 0.0,
+                });
+            }
+            Self::ShiftOnApproach {
+                offset,
+                distance,
+                speed,
+            } => {
+                pipe_group.insert(ShiftOnApproach {
+                    distance,
+                    speed,
+                    direction: if y > 0.0 { -1.0 } else { 1.0 },
+                    remaining: offset,
+                    started: false,
+                });
+            }
+        }
+    }
+}
+
+/// Moves the gap along a sine path around the height it spawned at.
+#[derive(Component, Debug)]
+pub struct Oscillate {
+    pub amplitude: f32,
+    pub period: f32,
+    elapsed: f32,
+}
+
+/// Narrows the gap of a [`PipeGroup`] from `widest` to `narrowest` and back.
+#[derive(Component, Debug)]
+pub struct OpenClose {
+    pub widest: f32,
+    pub narrowest: f32,
+    pub period: f32,
+    elapsed: f32,
+}
+
+/// Moves the pair vertically by a fixed amount once, when the bird gets close.
+#[derive(Component, Debug)]
+pub struct ShiftOnApproach {
+    pub distance: f32,
+    pub speed: f32,
+    /// `1.0` to move up, `-1.0` to move down.
+    pub direction: f32,
+    remaining: f32,
+    started: bool,
+}
+
+fn oscillate(time: Res<Time>, mut query: Query<(&mut Oscillate, &mut Velocity)>) {
+    for (mut oscillate, mut velocity) in query.iter_mut() {
+        let angular_speed = TAU / oscillate.period;
+        // the derivative of `amplitude * sin(angular_speed * t)`, sampled mid-step
+        let t = oscillate.elapsed + time.delta_seconds() / 2.0;
+        velocity.linvel.y = oscillate.amplitude * angular_speed * (angular_speed * t).cos();
+        oscillate.elapsed += time.delta_seconds();
+    }
+}
+
+fn open_and_close(time: Res<Time>, mut query: Query<(&mut OpenClose, &mut PipeGroup)>) {
+    for (mut open_close, mut pipe_group) in query.iter_mut() {
+        open_close.elapsed += time.delta_seconds();
+        let closed = (1.0 - (TAU * open_close.elapsed / open_close.period).cos()) / 2.0;
+        pipe_group.gap_size =
+            open_close.widest + (open_close.narrowest - open_close.widest) * closed;
+    }
+}
+
+fn shift_on_approach(
+    time: Res<Time>,
+    mut query: Query<(&mut ShiftOnApproach, &Transform, &mut Velocity)>,
+    q_bird: Query<&Transform, With<Bird>>,
+) {
+    // every bird flies at the same x
+    let Some(bird) = q_bird.iter().next() else {
+        return;
+    };
+    let delta = time.delta_seconds();
+
+    for (mut shift, transform, mut velocity) in query.iter_mut() {
+        if !shift.started {
+            shift.started = transform.translation.x - bird.translation.x <= shift.distance;
+        }
+        if !shift.started || shift.remaining <= 0.0 || delta <= 0.0 {
+            velocity.linvel.y = 0.0;
+            continue;
+        }
+
+        let step = f32::min(shift.speed * delta, shift.remaining);
+        shift.remaining -= step;
+        velocity.linvel.y = shift.direction * step / delta;
+    }
+}
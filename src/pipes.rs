@@ -1,26 +1,35 @@
-use bevy::prelude::*;
-use bevy::window::PrimaryWindow;
+use bevy::{ecs::system::EntityCommands, prelude::*};
 use bevy_rapier2d::prelude::*;
 use rand::Rng;
 use std::time::Duration;
 
+use crate::difficulty::DifficultyCurve;
+use crate::flappy::Lethal;
+use crate::interpolation::Interpolated;
+use crate::levels::LevelRun;
+use crate::obstacles::Obstacle;
+use crate::pause::PauseState;
+use crate::pipe_behaviours::PipeBehaviour;
+use crate::players::MAX_PLAYERS;
+use crate::playfield::Playfield;
+use crate::power_ups::{ScrollSpeed, Scrolls};
+use crate::rng::GameRng;
+use crate::tuning::Tuning;
 use crate::AppState;
 use crate::Score;
+use crate::SimulationSet;
 
 const SPRITE_SIZE: f32 = 250.0;
 
 const PIPE_HEIGHT: f32 = SPRITE_SIZE * 8.0;
-const PIPE_WIDTH: f32 = SPRITE_SIZE;
+pub const PIPE_WIDTH: f32 = SPRITE_SIZE;
 
-const GAP_SIZE: f32 = 600.0;
+const GAP_SENSOR_WIDTH: f32 = 50.0;
 
-const PIPE_BASE_SPEED: f32 = -300.0;
-const PIPE_SPEED_INCREASE_RATE: f32 = 0.05;
-
-const PIPE_SPAWN_FIRST: f32 = 1.0;
-const PIPE_SPAWN_INTERVAL_INITIAL: f32 = 5.0;
-const PIPE_SPAWN_INTERVAL_FINAL: f32 = 2.0;
-const PIPE_SPAWN_INTERVAL_INCREASE_RATE: f32 = 0.05;
+/// Narrowest gap the generator makes, two and a half times the bird's height.
+pub const MIN_GAP_SIZE: f32 = 250.0;
+// keeps both pipes of a pair partly on screen
+const GAP_MARGIN: f32 = 20.0;
 
 pub struct PipesPlugin;
 
@@ -31,16 +40,23 @@ impl Plugin for PipesPlugin {
             (spawn_pipe_despawn_area, setup_spawn_pipe),
         )
         .add_systems(
-            Update,
-            (spawn_pipe, detect_pipe_despawn_and_pipes_collision)
-                .run_if(in_state(AppState::InGame)),
+            FixedUpdate,
+            (
+                // levels place their own pipes
+                spawn_pipe.run_if(not(resource_exists::<LevelRun>())),
+                fit_pipes_to_gap,
+                detect_pipe_despawn_and_pipes_collision,
+            )
+                .in_set(SimulationSet::Gameplay)
+                .run_if(in_state(AppState::InGame))
+                .run_if(in_state(PauseState::Running)),
         )
         .add_systems(OnExit(AppState::InGame), despawn);
     }
 }
 
 #[derive(Resource)]
-struct PipesSpawnConfig {
+pub struct PipesSpawnConfig {
     timer: Timer,
 }
 
@@ -50,36 +66,42 @@ pub struct PipeTop;
 #[derive(Component)]
 pub struct PipeBottom;
 
-#[derive(Component)]
+#[derive(Component, Default)]
 pub struct GapSensor {
-    pub counted: bool,
+    /// Which players have scored the gap.
+    pub counted: [bool; MAX_PLAYERS],
 }
 
+/// A pair of pipes and the sensor between them.
+///
+/// Changing `gap_size` moves both pipes and resizes the sensor to match.
 #[derive(Component)]
-struct PipeGroup;
+pub struct PipeGroup {
+    pub gap_size: f32,
+}
 
+/// Sensor past the left edge of the playfield; obstacles that reach it are despawned.
 #[derive(Component)]
-struct PipeDespawnArea;
+pub struct PipeDespawnArea;
+
+/// Distance from the centre of a gap of `gap_size` to the centre of each of its pipes.
+pub fn pipe_offset(gap_size: f32) -> f32 {
+    PIPE_HEIGHT / 2.0 + gap_size / 2.0
+}
 
-fn setup_spawn_pipe(mut commands: Commands) {
+fn setup_spawn_pipe(mut commands: Commands, tuning: Res<Tuning>) {
     commands.insert_resource(PipesSpawnConfig {
         timer: Timer::new(
-            Duration::from_secs_f32(PIPE_SPAWN_FIRST),
+            Duration::from_secs_f32(tuning.pipes.spawn_first),
             TimerMode::Repeating,
         ),
     });
 }
 
-fn spawn_pipe_despawn_area(
-    mut commands: Commands,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-) {
-    let Ok(window) = window_query.get_single() else {
-        return;
-    };
-    let x_position = -(window.width() + PIPE_WIDTH + 20.0);
+fn spawn_pipe_despawn_area(mut commands: Commands, playfield: Res<Playfield>) {
+    let x_position = -(playfield.width + PIPE_WIDTH + 20.0);
     let width = 50.0;
-    let height = window.height() * 2.0;
+    let height = playfield.height * 2.0;
 
     commands
         .spawn(SpriteBundle {
@@ -101,147 +123,229 @@ fn spawn_pipe_despawn_area(
         .insert(PipeDespawnArea);
 }
 
-fn spawn_pipe(
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_pipe(
     mut commands: Commands,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    playfield: Res<Playfield>,
     time: Res<Time>,
     mut config: ResMut<PipesSpawnConfig>,
     asset_server: Res<AssetServer>,
     score: Res<Score>,
+    mut game_rng: ResMut<GameRng>,
+    tuning: Res<Tuning>,
+    curve: Res<DifficultyCurve>,
+    scroll_speed: Res<ScrollSpeed>,
 ) {
-    config.timer.tick(time.delta());
+    // slowing the clock with the pipes keeps them as far apart
+    config.timer.tick(time.delta().mul_f32(scroll_speed.0));
 
     if config.timer.finished() {
-        let mut rng = rand::thread_rng();
-        let Ok(window) = window_query.get_single() else {
-            return;
-        };
-        let new_timer_interval = f32::max(
-            PIPE_SPAWN_INTERVAL_INITIAL
-                * (1.0 - score.0 as f32 * PIPE_SPAWN_INTERVAL_INCREASE_RATE),
-            PIPE_SPAWN_INTERVAL_FINAL,
-        );
+        let level = tuning.difficulty.level(&curve, score.0 as f32);
         config.timer = Timer::new(
-            Duration::from_secs_f32(new_timer_interval),
+            Duration::from_secs_f32(level.spawn_interval),
             TimerMode::Repeating,
         );
+        let initial_position_x = playfield.width + PIPE_WIDTH;
+        let velocity = Vec2::new(level.speed * scroll_speed.0, 0.0);
 
-        let sensor_width = 50.0;
+        // other obstacles take some of the pipe pairs' turns, the first whose roll succeeds
+        let rng = &mut game_rng.obstacles;
+        let obstacle: Option<&Obstacle> = tuning
+            .obstacles
+            .iter()
+            .filter(|spawn| score.0 >= spawn.from_score)
+            .find(|spawn| rng.gen_bool(spawn.chance))
+            .map(|spawn| &spawn.obstacle);
+        if let Some(obstacle) = obstacle {
+            let height_variation = f32::max(
+                (playfield.height - obstacle.vertical_reach() - GAP_MARGIN)
+                    * level.vertical_variance,
+                0.0,
+            );
+            let y = rng.gen_range(-height_variation..=height_variation);
+            obstacle.spawn(
+                &mut commands,
+                &asset_server,
+                Vec2::new(initial_position_x, y),
+                velocity,
+                rng,
+            );
+            return;
+        }
+
+        let rng = &mut game_rng.pipes;
+        let gap_size = level.gap_size.clamp(
+            MIN_GAP_SIZE,
+            f32::max(playfield.height * 2.0 - GAP_MARGIN * 2.0, MIN_GAP_SIZE),
+        );
 
-        let initial_position_x = window.width() + PIPE_WIDTH;
-        let initial_height_variation = window.height() - GAP_SIZE / 2.0 - 20.0;
-        let initial_position_y = rng.gen_range(-initial_height_variation..initial_height_variation);
+        // at most one behaviour per pair, the first whose roll succeeds
+        let behaviour: Option<&PipeBehaviour> = tuning
+            .pipes
+            .behaviours
+            .iter()
+            .filter(|spawn| score.0 >= spawn.from_score)
+            .find(|spawn| rng.gen_bool(spawn.chance))
+            .map(|spawn| &spawn.behaviour);
+        let vertical_reach = behaviour.map_or(0.0, PipeBehaviour::vertical_reach);
 
-        let pipe_speed = PIPE_BASE_SPEED * (1.0 + score.0 as f32 * PIPE_SPEED_INCREASE_RATE);
+        let initial_height_variation = f32::max(
+            (playfield.height - gap_size / 2.0 - GAP_MARGIN) * level.vertical_variance
+                - vertical_reach,
+            0.0,
+        );
+        let initial_position_y =
+            rng.gen_range(-initial_height_variation..=initial_height_variation);
+
+        let mut pipe_group = spawn_pipe_pair(
+            &mut commands,
+            &asset_server,
+            Vec2::new(initial_position_x, initial_position_y),
+            velocity,
+            gap_size,
+        );
+        if let Some(behaviour) = behaviour {
+            behaviour.insert(&mut pipe_group, initial_position_y, gap_size);
+        }
+    }
+}
 
-        commands
-            .spawn(SpriteBundle {
-                transform: Transform::from_xyz(initial_position_x, initial_position_y, 1.0),
-                sprite: Sprite {
-                    color: Color::NONE.into(),
+/// Spawns a pair of pipes with the gap between them centred on `position`, moving at `velocity`.
+pub fn spawn_pipe_pair<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    asset_server: &AssetServer,
+    position: Vec2,
+    velocity: Vec2,
+    gap_size: f32,
+) -> EntityCommands<'w, 's, 'a> {
+    let mut pipe_group = commands.spawn(SpriteBundle {
+        transform: Transform::from_translation(position.extend(1.0)),
+        sprite: Sprite {
+            color: Color::NONE.into(),
+            ..default()
+        },
+        ..default()
+    });
+    pipe_group
+        .insert(RigidBody::KinematicVelocityBased)
+        .insert(Velocity::linear(velocity))
+        .insert(LockedAxes::ROTATION_LOCKED)
+        .insert(Interpolated::default())
+        .insert(PipeGroup { gap_size })
+        .insert(Scrolls)
+        .with_children(|parent| {
+            // pipe top
+            parent
+                .spawn(SpriteBundle {
+                    transform: Transform::from_xyz(0.0, pipe_offset(gap_size), 0.0),
                     ..default()
-                },
-                ..default()
-            })
-            .insert(RigidBody::KinematicVelocityBased)
-            .insert(Velocity::linear(Vec2::new(pipe_speed, 0.0)))
-            .insert(LockedAxes::ROTATION_LOCKED)
-            .insert(PipeGroup)
-            .with_children(|parent| {
-                // pipe top
-                parent
-                    .spawn(SpriteBundle {
-                        transform: Transform::from_xyz(
-                            0.0,
-                            PIPE_HEIGHT / 2.0 + GAP_SIZE / 2.0,
-                            0.0,
-                        ),
-                        ..default()
-                    })
-                    .insert(Collider::cuboid(PIPE_WIDTH / 2.0, PIPE_HEIGHT / 2.0))
-                    .insert(CollisionGroups::new(
-                        Group::from_bits(0b0110).unwrap(),
-                        Group::from_bits(0b0110).unwrap(),
-                    ))
-                    .insert(ActiveEvents::COLLISION_EVENTS)
-                    .insert(PipeTop)
-                    .with_children(|parent| {
-                        let number_of_sprites = (PIPE_HEIGHT / SPRITE_SIZE) as u32;
-                        let initial_position = -(PIPE_HEIGHT / 2.0 - SPRITE_SIZE / 2.0);
-                        for pipe_index in 0..number_of_sprites {
-                            parent.spawn(SpriteBundle {
-                                transform: Transform::from_xyz(
-                                    0.0,
-                                    initial_position + (pipe_index as f32 * SPRITE_SIZE),
-                                    0.0,
-                                ),
-                                texture: asset_server.load("pipe.png"),
-                                sprite: Sprite {
-                                    custom_size: Some(Vec2::new(PIPE_WIDTH, SPRITE_SIZE)),
-                                    color: Color::rgb(60.0 / 255.0, 185.0 / 255.0, 120.0 / 255.0),
-                                    ..default()
-                                },
+                })
+                .insert(Collider::cuboid(PIPE_WIDTH / 2.0, PIPE_HEIGHT / 2.0))
+                .insert(CollisionGroups::new(
+                    Group::from_bits(0b0110).unwrap(),
+                    Group::from_bits(0b0110).unwrap(),
+                ))
+                .insert(ActiveEvents::COLLISION_EVENTS)
+                .insert(PipeTop)
+                .insert(Lethal)
+                .with_children(|parent| {
+                    let number_of_sprites = (PIPE_HEIGHT / SPRITE_SIZE) as u32;
+                    let initial_position = -(PIPE_HEIGHT / 2.0 - SPRITE_SIZE / 2.0);
+                    for pipe_index in 0..number_of_sprites {
+                        parent.spawn(SpriteBundle {
+                            transform: Transform::from_xyz(
+                                0.0,
+                                initial_position + (pipe_index as f32 * SPRITE_SIZE),
+                                0.0,
+                            ),
+                            texture: asset_server.load("pipe.png"),
+                            sprite: Sprite {
+                                custom_size: Some(Vec2::new(PIPE_WIDTH, SPRITE_SIZE)),
+                                color: Color::rgb(60.0 / 255.0, 185.0 / 255.0, 120.0 / 255.0),
                                 ..default()
-                            });
-                        }
-                    });
-                // Gap Sensor
-                parent
-                    .spawn(SpriteBundle {
-                        transform: Transform::from_xyz(0.0, 0.0, 0.0),
-                        sprite: Sprite {
-                            color: Color::NONE.into(),
-                            custom_size: Some(Vec2::new(sensor_width, GAP_SIZE)),
+                            },
                             ..default()
-                        },
-                        ..default()
-                    })
-                    .insert(Collider::cuboid(sensor_width / 2.0, GAP_SIZE / 2.0))
-                    .insert(CollisionGroups::new(
-                        Group::from_bits(0b0110).unwrap(),
-                        Group::from_bits(0b0110).unwrap(),
-                    ))
-                    .insert(ActiveEvents::COLLISION_EVENTS)
-                    .insert(Sensor)
-                    .insert(GapSensor { counted: false });
-                // pipe bottom
-                parent
-                    .spawn(SpriteBundle {
-                        transform: Transform::from_xyz(
-                            0.0,
-                            -(PIPE_HEIGHT / 2.0 + GAP_SIZE / 2.0),
-                            0.0,
-                        ),
+                        });
+                    }
+                });
+            // Gap Sensor
+            parent
+                .spawn(SpriteBundle {
+                    transform: Transform::from_xyz(0.0, 0.0, 0.0),
+                    sprite: Sprite {
+                        color: Color::NONE.into(),
+                        custom_size: Some(Vec2::new(GAP_SENSOR_WIDTH, gap_size)),
                         ..default()
-                    })
-                    .insert(Collider::cuboid(PIPE_WIDTH / 2.0, PIPE_HEIGHT / 2.0))
-                    .insert(CollisionGroups::new(
-                        Group::from_bits(0b0110).unwrap(),
-                        Group::from_bits(0b0110).unwrap(),
-                    ))
-                    .insert(ActiveEvents::COLLISION_EVENTS)
-                    .insert(PipeBottom)
-                    .with_children(|parent| {
-                        let number_of_sprites = (PIPE_HEIGHT / SPRITE_SIZE) as u32;
-                        let initial_position = -(PIPE_HEIGHT / 2.0 - SPRITE_SIZE / 2.0);
-                        for pipe_index in 0..number_of_sprites {
-                            parent.spawn(SpriteBundle {
-                                transform: Transform::from_xyz(
-                                    0.0,
-                                    initial_position + (pipe_index as f32 * SPRITE_SIZE),
-                                    0.0,
-                                ),
-                                texture: asset_server.load("pipe.png"),
-                                sprite: Sprite {
-                                    custom_size: Some(Vec2::new(PIPE_WIDTH, SPRITE_SIZE)),
-                                    color: Color::rgb(60.0 / 255.0, 185.0 / 255.0, 120.0 / 255.0),
-                                    ..default()
-                                },
+                    },
+                    ..default()
+                })
+                .insert(Collider::cuboid(GAP_SENSOR_WIDTH / 2.0, gap_size / 2.0))
+                .insert(CollisionGroups::new(
+                    Group::from_bits(0b0110).unwrap(),
+                    Group::from_bits(0b0110).unwrap(),
+                ))
+                .insert(ActiveEvents::COLLISION_EVENTS)
+                .insert(Sensor)
+                .insert(GapSensor::default());
+            // pipe bottom
+            parent
+                .spawn(SpriteBundle {
+                    transform: Transform::from_xyz(0.0, -(pipe_offset(gap_size)), 0.0),
+                    ..default()
+                })
+                .insert(Collider::cuboid(PIPE_WIDTH / 2.0, PIPE_HEIGHT / 2.0))
+                .insert(CollisionGroups::new(
+                    Group::from_bits(0b0110).unwrap(),
+                    Group::from_bits(0b0110).unwrap(),
+                ))
+                .insert(ActiveEvents::COLLISION_EVENTS)
+                .insert(PipeBottom)
+                .insert(Lethal)
+                .with_children(|parent| {
+                    let number_of_sprites = (PIPE_HEIGHT / SPRITE_SIZE) as u32;
+                    let initial_position = -(PIPE_HEIGHT / 2.0 - SPRITE_SIZE / 2.0);
+                    for pipe_index in 0..number_of_sprites {
+                        parent.spawn(SpriteBundle {
+                            transform: Transform::from_xyz(
+                                0.0,
+                                initial_position + (pipe_index as f32 * SPRITE_SIZE),
+                                0.0,
+                            ),
+                            texture: asset_server.load("pipe.png"),
+                            sprite: Sprite {
+                                custom_size: Some(Vec2::new(PIPE_WIDTH, SPRITE_SIZE)),
+                                color: Color::rgb(60.0 / 255.0, 185.0 / 255.0, 120.0 / 255.0),
                                 ..default()
-                            });
-                        }
-                    });
-            });
+                            },
+                            ..default()
+                        });
+                    }
+                });
+        });
+    pipe_group
+}
+
+/// Moves the pipes and resizes the sensor of pairs whose gap changed.
+pub fn fit_pipes_to_gap(
+    q_pipe_group: Query<(&PipeGroup, &Children), Changed<PipeGroup>>,
+    mut q_pipe_top: Query<&mut Transform, With<PipeTop>>,
+    mut q_pipe_bottom: Query<&mut Transform, (With<PipeBottom>, Without<PipeTop>)>,
+    mut q_gap_sensor: Query<(&mut Collider, &mut Sprite), With<GapSensor>>,
+) {
+    for (pipe_group, children) in q_pipe_group.iter() {
+        let gap_size = pipe_group.gap_size;
+        for &child in children.iter() {
+            if let Ok(mut transform) = q_pipe_top.get_mut(child) {
+                transform.translation.y = pipe_offset(gap_size);
+            }
+            if let Ok(mut transform) = q_pipe_bottom.get_mut(child) {
+                transform.translation.y = -pipe_offset(gap_size);
+            }
+            if let Ok((mut collider, mut sprite)) = q_gap_sensor.get_mut(child) {
+                *collider = Collider::cuboid(GAP_SENSOR_WIDTH / 2.0, gap_size / 2.0);
+                sprite.custom_size = Some(Vec2::new(GAP_SENSOR_WIDTH, gap_size));
+            }
+        }
     }
 }
 
@@ -267,9 +371,7 @@ fn detect_pipe_despawn_and_pipes_collision(
 fn despawn(
     mut commands: Commands,
     query: Query<Entity, Or<(With<PipeGroup>, With<PipeDespawnArea>)>>,
-    mut score: ResMut<Score>,
 ) {
-    score.0 = 0;
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
     }
@@ -0,0 +1,162 @@
+use bevy::prelude::*;
+
+use crate::game_modes::GameMode;
+use crate::levels::LevelRun;
+use crate::lives;
+use crate::pause::PauseState;
+use crate::AppState;
+use crate::SimulationSet;
+
+/// Most birds a run can have, one per set of controls.
+pub const MAX_PLAYERS: usize = 4;
+/// Tints telling the players' birds apart in a multiplayer run.
+pub const PLAYER_TINTS: [Color; MAX_PLAYERS] = [
+    Color::rgb(0.95, 0.3, 0.25),
+    Color::rgb(0.25, 0.5, 0.95),
+    Color::rgb(0.3, 0.8, 0.35),
+    Color::rgb(0.95, 0.8, 0.2),
+];
+/// What flaps each player's bird in a multiplayer run.
+pub const PLAYER_CONTROLS: [Control; MAX_PLAYERS] = [
+    Control::Key(KeyCode::Space),
+    Control::Key(KeyCode::Return),
+    Control::Mouse,
+    Control::Key(KeyCode::W),
+];
+// vertical distance between the birds at the start of a multiplayer run
+const PLAYER_SPACING: f32 = 60.0;
+
+/// Keeps each player's score and ends multiplayer runs once a winner is decided.
+pub struct PlayersPlugin;
+
+impl Plugin for PlayersPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerCount>()
+            .init_resource::<Scoreboard>()
+            .add_systems(OnEnter(AppState::InGame), reset_scoreboard)
+            .add_systems(
+                FixedUpdate,
+                end_multiplayer_run
+                    .after(lives::lose_life)
+                    .in_set(SimulationSet::Gameplay)
+                    .run_if(multiplayer)
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+/// How many birds the next run has, each flown by a different player on the same screen.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerCount(pub usize);
+
+impl Default for PlayerCount {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+impl PlayerCount {
+    pub fn next(self) -> Self {
+        Self(self.0 % MAX_PLAYERS + 1)
+    }
+}
+
+/// A key or the mouse, whose press flaps a player's bird.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Key(KeyCode),
+    /// Either mouse button.
+    Mouse,
+}
+
+impl Control {
+    pub fn just_pressed(self, key_buttons: &Input<KeyCode>, buttons: &Input<MouseButton>) -> bool {
+        match self {
+            Self::Key(key_code) => key_buttons.just_pressed(key_code),
+            Self::Mouse => buttons.any_just_pressed([MouseButton::Left, MouseButton::Right]),
+        }
+    }
+
+    /// How the control is shown to players.
+    pub fn label(self) -> String {
+        match self {
+            Self::Key(KeyCode::Return) => "Enter".to_string(),
+            Self::Key(key_code) => format!("{key_code:?}"),
+            Self::Mouse => "Mouse".to_string(),
+        }
+    }
+}
+
+/// Each player's score in the current run, and whether their bird is still flying.
+#[derive(Resource, Debug, Default)]
+pub struct Scoreboard {
+    pub scores: Vec<u32>,
+    pub flying: Vec<bool>,
+}
+
+impl Scoreboard {
+    /// Adds `points` to `player`'s score and returns their new score.
+    pub fn award(&mut self, player: usize, points: u32) -> u32 {
+        match self.scores.get_mut(player) {
+            Some(score) => {
+                *score += points;
+                *score
+            }
+            None => 0,
+        }
+    }
+
+    /// Takes `player`'s bird out of the run.
+    pub fn eliminate(&mut self, player: usize) {
+        if let Some(flying) = self.flying.get_mut(player) {
+            *flying = false;
+        }
+    }
+
+    /// The last bird still flying, unless the run was `timed`. Otherwise, or when the last
+    /// birds crashed together, the highest score. `None` for a draw.
+    pub fn winner(&self, timed: bool) -> Option<usize> {
+        let flying: Vec<usize> = (0..self.flying.len())
+            .filter(|player| self.flying[*player])
+            .collect();
+        if !timed && flying.len() == 1 {
+            return Some(flying[0]);
+        }
+        let best = self.scores.iter().max()?;
+        let mut leaders = (0..self.scores.len()).filter(|player| self.scores[*player] == *best);
+        match (leaders.next(), leaders.next()) {
+            (Some(leader), None) => Some(leader),
+            _ => None,
+        }
+    }
+}
+
+/// Where `player`'s bird starts, spread around the middle of the playfield.
+pub fn start_height(player: usize, players: PlayerCount) -> f32 {
+    (player as f32 - (players.0 as f32 - 1.0) / 2.0) * PLAYER_SPACING
+}
+
+/// Run condition for runs with more than one bird.
+pub fn multiplayer(players: Res<PlayerCount>) -> bool {
+    players.0 > 1
+}
+
+fn reset_scoreboard(players: Res<PlayerCount>, mut scoreboard: ResMut<Scoreboard>) {
+    scoreboard.scores = vec![0; players.0];
+    scoreboard.flying = vec![true; players.0];
+}
+
+fn end_multiplayer_run(
+    mode: Res<GameMode>,
+    level_run: Option<Res<LevelRun>>,
+    scoreboard: Res<Scoreboard>,
+    mut app_state: ResMut<NextState<AppState>>,
+) {
+    let flying = scoreboard.flying.iter().filter(|flying| **flying).count();
+    // a timed run goes on while anyone is flying, the clock decides it
+    let timed = *mode == GameMode::Timed && level_run.is_none();
+    if flying == 0 || (!timed && flying == 1) {
+        app_state.set(AppState::GameOver);
+    }
+}
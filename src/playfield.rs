@@ -0,0 +1,46 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+const PLAYFIELD_WIDTH: f32 = 1200.0;
+const PLAYFIELD_HEIGHT: f32 = 600.0;
+
+pub struct PlayfieldPlugin;
+
+impl Plugin for PlayfieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Playfield>()
+            .add_systems(PreStartup, sync_with_window)
+            .add_systems(First, sync_with_window);
+    }
+}
+
+/// Logical size of the area the game is played in.
+///
+/// Follows the primary window when there is one, otherwise keeps the size it was
+/// inserted with, so the spawn systems behave the same with or without a renderer.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct Playfield {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for Playfield {
+    fn default() -> Self {
+        Self {
+            width: PLAYFIELD_WIDTH,
+            height: PLAYFIELD_HEIGHT,
+        }
+    }
+}
+
+fn sync_with_window(
+    mut playfield: ResMut<Playfield>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+) {
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    if playfield.width != window.width() || playfield.height != window.height() {
+        playfield.width = window.width();
+        playfield.height = window.height();
+    }
+}
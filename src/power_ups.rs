@@ -0,0 +1,332 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::characters::CharacterDefinition;
+use crate::flappy::Flappy;
+use crate::levels::LevelRun;
+use crate::pause::PauseState;
+use crate::pipes::{self, PipeGroup};
+use crate::rng::GameRng;
+use crate::tuning::Tuning;
+use crate::AppState;
+use crate::SimulationSet;
+
+const PICKUP_SIZE: f32 = 60.0;
+// past the pipe, clear of any coins in the gap
+const PICKUP_OFFSET_X: f32 = 300.0;
+
+/// Pickups that grant timed effects, spawned with some pipe pairs.
+pub struct PowerUpsPlugin;
+
+impl Plugin for PowerUpsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PowerUpRegistry>()
+            .init_resource::<ActivePowerUps>()
+            .init_resource::<ScrollSpeed>()
+            .init_resource::<ScoreMultiplier>()
+            .add_systems(OnEnter(AppState::InGame), clear_power_ups)
+            .add_systems(OnExit(AppState::InGame), clear_power_ups)
+            .add_systems(
+                FixedUpdate,
+                tick_power_ups
+                    .in_set(SimulationSet::PowerUps)
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    spawn_pickups
+                        .after(pipes::spawn_pipe)
+                        .run_if(not(resource_exists::<LevelRun>())),
+                    collect_pickups,
+                )
+                    .in_set(SimulationSet::Gameplay)
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PowerUpKind {
+    /// Absorbs one lethal hit.
+    Shield,
+    /// Slows down the pipes and clouds.
+    SlowMotion,
+    /// Makes the bird smaller.
+    Shrink,
+    /// Multiplies the points for each pipe passed.
+    ScoreMultiplier,
+}
+
+impl PowerUpKind {
+    pub const ALL: [Self; 4] = [
+        Self::Shield,
+        Self::SlowMotion,
+        Self::Shrink,
+        Self::ScoreMultiplier,
+    ];
+}
+
+/// How a kind of power-up looks, in its pickup and in the HUD.
+pub struct PowerUpEffect {
+    pub label: &'static str,
+    pub color: Color,
+}
+
+/// Every kind of power-up the game knows about. Pickups are only spawned for registered kinds.
+#[derive(Resource)]
+pub struct PowerUpRegistry {
+    effects: BTreeMap<PowerUpKind, PowerUpEffect>,
+}
+
+impl Default for PowerUpRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            effects: BTreeMap::new(),
+        };
+        registry.register(
+            PowerUpKind::Shield,
+            PowerUpEffect {
+                label: "Shield",
+                color: Color::rgb(0.3, 0.6, 1.0),
+            },
+        );
+        registry.register(
+            PowerUpKind::SlowMotion,
+            PowerUpEffect {
+                label: "Slow-mo",
+                color: Color::rgb(0.6, 0.3, 0.9),
+            },
+        );
+        registry.register(
+            PowerUpKind::Shrink,
+            PowerUpEffect {
+                label: "Shrink",
+                color: Color::rgb(0.2, 0.8, 0.4),
+            },
+        );
+        registry.register(
+            PowerUpKind::ScoreMultiplier,
+            PowerUpEffect {
+                label: "Score x",
+                color: Color::rgb(1.0, 0.4, 0.2),
+            },
+        );
+        registry
+    }
+}
+
+impl PowerUpRegistry {
+    pub fn register(&mut self, kind: PowerUpKind, effect: PowerUpEffect) {
+        self.effects.insert(kind, effect);
+    }
+
+    pub fn get(&self, kind: PowerUpKind) -> Option<&PowerUpEffect> {
+        self.effects.get(&kind)
+    }
+
+    pub fn kinds(&self) -> impl Iterator<Item = PowerUpKind> + '_ {
+        self.effects.keys().copied()
+    }
+}
+
+/// Power-ups in effect and the time each has left.
+///
+/// In a multiplayer run every bird shares them: whoever picks one up, it works for all the
+/// birds, and a shield is used up by the first crash, saving every bird that crashed in that step.
+#[derive(Resource, Default)]
+pub struct ActivePowerUps(BTreeMap<PowerUpKind, Timer>);
+
+impl ActivePowerUps {
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.0.contains_key(&kind)
+    }
+
+    /// Starts `kind`, or restarts it if it is already active.
+    pub fn activate(&mut self, kind: PowerUpKind, duration: Duration) {
+        self.0.insert(kind, Timer::new(duration, TimerMode::Once));
+    }
+
+    /// Ends `kind` early, returning whether it was active.
+    pub fn consume(&mut self, kind: PowerUpKind) -> bool {
+        self.0.remove(&kind).is_some()
+    }
+
+    pub fn remaining(&self) -> impl Iterator<Item = (PowerUpKind, Duration)> + '_ {
+        self.0
+            .iter()
+            .map(|(kind, timer)| (*kind, timer.duration() - timer.elapsed()))
+    }
+}
+
+/// How fast the world scrolls past, lowered by slow motion. Applies to everything
+/// tagged [`Scrolls`], including what spawns while it is in effect.
+#[derive(Resource)]
+pub struct ScrollSpeed(pub f32);
+
+impl Default for ScrollSpeed {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// Points each passed pipe is worth.
+#[derive(Resource)]
+pub struct ScoreMultiplier(pub u32);
+
+impl Default for ScoreMultiplier {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// Moves with the world, so slow motion slows it down.
+#[derive(Component)]
+pub struct Scrolls;
+
+#[derive(Component)]
+pub struct PowerUpPickup(pub PowerUpKind);
+
+fn clear_power_ups(mut active: ResMut<ActivePowerUps>) {
+    active.0.clear();
+}
+
+fn tick_power_ups(
+    time: Res<Time>,
+    mut active: ResMut<ActivePowerUps>,
+    tuning: Res<Tuning>,
+    mut scroll_speed: ResMut<ScrollSpeed>,
+    mut score_multiplier: ResMut<ScoreMultiplier>,
+    mut q_scrolls: Query<&mut Velocity, With<Scrolls>>,
+    mut q_flappy: Query<(&CharacterDefinition, &mut Collider, &mut Sprite), With<Flappy>>,
+) {
+    if !active.0.is_empty() {
+        for timer in active.0.values_mut() {
+            timer.tick(time.delta());
+        }
+        active.0.retain(|_, timer| !timer.finished());
+    }
+    let tuning = &tuning.power_ups;
+
+    let speed = if active.is_active(PowerUpKind::SlowMotion) {
+        tuning.slow_motion_scale
+    } else {
+        1.0
+    };
+    if scroll_speed.0 != speed {
+        for mut velocity in q_scrolls.iter_mut() {
+            velocity.linvel.x *= speed / scroll_speed.0;
+        }
+        scroll_speed.0 = speed;
+    }
+
+    let multiplier = if active.is_active(PowerUpKind::ScoreMultiplier) {
+        tuning.multiplier
+    } else {
+        1
+    };
+    if score_multiplier.0 != multiplier {
+        score_multiplier.0 = multiplier;
+    }
+
+    let scale = if active.is_active(PowerUpKind::Shrink) {
+        tuning.shrink_scale
+    } else {
+        1.0
+    };
+    for (character, mut collider, mut sprite) in q_flappy.iter_mut() {
+        let size = Some(Vec2::splat(character.sprite_size * scale));
+        if sprite.custom_size != size {
+            *collider = character.scaled_collider(scale);
+            sprite.custom_size = size;
+        }
+    }
+}
+
+/// Gives some new pipe pairs a pickup, just past the pipes.
+fn spawn_pickups(
+    mut commands: Commands,
+    q_new_pipe_group: Query<Entity, Added<PipeGroup>>,
+    mut game_rng: ResMut<GameRng>,
+    registry: Res<PowerUpRegistry>,
+    tuning: Res<Tuning>,
+) {
+    let tuning = &tuning.power_ups;
+    let kinds: Vec<PowerUpKind> = registry.kinds().collect();
+    let Ok(weights) = WeightedIndex::new(kinds.iter().map(|kind| tuning.spawn(*kind).weight))
+    else {
+        return;
+    };
+
+    for entity in q_new_pipe_group.iter() {
+        let rng = &mut game_rng.power_ups;
+        if !rng.gen_bool(tuning.chance) {
+            continue;
+        }
+        let kind = kinds[weights.sample(rng)];
+        let Some(effect) = registry.get(kind) else {
+            continue;
+        };
+
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn(pickup_bundle(
+                kind,
+                effect,
+                Vec3::new(PICKUP_OFFSET_X, 0.0, 0.5),
+            ));
+        });
+    }
+}
+
+/// A pickup for `kind` at `translation`, for spawning as part of something that moves it.
+pub fn pickup_bundle(kind: PowerUpKind, effect: &PowerUpEffect, translation: Vec3) -> impl Bundle {
+    (
+        SpriteBundle {
+            transform: Transform::from_translation(translation),
+            sprite: Sprite {
+                color: effect.color,
+                custom_size: Some(Vec2::splat(PICKUP_SIZE)),
+                ..default()
+            },
+            ..default()
+        },
+        Collider::ball(PICKUP_SIZE / 2.0),
+        // a group of their own that only the bird collides with
+        CollisionGroups::new(
+            Group::from_bits(0b100000).unwrap(),
+            Group::from_bits(0b0100).unwrap(),
+        ),
+        Sensor,
+        ActiveEvents::COLLISION_EVENTS,
+        PowerUpPickup(kind),
+    )
+}
+
+fn collect_pickups(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    q_flappy: Query<Entity, With<Flappy>>,
+    q_pickup: Query<&PowerUpPickup>,
+    mut active: ResMut<ActivePowerUps>,
+    tuning: Res<Tuning>,
+) {
+    for collision_event in collision_events.read() {
+        let CollisionEvent::Started(entity1, entity2, _) = collision_event else {
+            continue;
+        };
+        for (flappy, pickup) in [(*entity1, *entity2), (*entity2, *entity1)] {
+            let (true, Ok(PowerUpPickup(kind))) = (q_flappy.contains(flappy), q_pickup.get(pickup))
+            else {
+                continue;
+            };
+            let duration = tuning.power_ups.spawn(*kind).duration;
+            active.activate(*kind, Duration::from_secs_f32(duration));
+            commands.entity(pickup).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use bevy::prelude::*;
+use std::{fs, io, path::Path, path::PathBuf};
+use thiserror::Error;
+
+use crate::characters::SelectedCharacter;
+use crate::flappy::Flap;
+use crate::game_modes::GameMode;
+use crate::levels::CurrentLevel;
+use crate::pause::PauseState;
+use crate::players::PlayerCount;
+use crate::rng::{self, GameRng};
+use crate::tuning::Tuning;
+use crate::AppState;
+use crate::SimulationSet;
+use crate::SimulationTick;
+
+const REPLAY_MAGIC: &[u8; 4] = b"FLRP";
+// 2: added the character
+// 3: added the game mode
+// 4: added the level and the tuning fingerprint
+const REPLAY_VERSION: u8 = 4;
+// how long playback waits for the tuning it was recorded with to load
+const TUNING_WAIT_SECS: f32 = 10.0;
+
+/// Records flaps into [`ReplayRecorder`] and feeds them back from [`ReplayPlayback`].
+/// Neither happens unless the matching resource is inserted, and only single-player runs
+/// are recorded.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            start_recording
+                .after(rng::reseed)
+                .run_if(resource_exists::<ReplayRecorder>())
+                .run_if(resource_equals(PlayerCount(1))),
+        )
+        .add_systems(
+            FixedUpdate,
+            record_flaps
+                .in_set(SimulationSet::Gameplay)
+                .run_if(resource_exists::<ReplayRecorder>())
+                .run_if(resource_equals(PlayerCount(1)))
+                .run_if(in_state(AppState::InGame))
+                .run_if(in_state(PauseState::Running)),
+        )
+        .add_systems(
+            OnExit(AppState::InGame),
+            (
+                save_recording
+                    .run_if(resource_exists::<ReplayRecorder>())
+                    .run_if(resource_equals(PlayerCount(1))),
+                finish_playback.run_if(resource_exists::<ReplayPlayback>()),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                start_playback.run_if(in_state(AppState::GameStart)),
+                stop_playback_on_new_tuning
+                    .run_if(resource_changed::<Tuning>())
+                    .run_if(in_state(AppState::InGame)),
+            )
+                .run_if(resource_exists::<ReplayPlayback>()),
+        )
+        .add_systems(
+            FixedUpdate,
+            play_flaps
+                .in_set(SimulationSet::Input)
+                .run_if(resource_exists::<ReplayPlayback>())
+                .run_if(in_state(AppState::InGame))
+                .run_if(in_state(PauseState::Running)),
+        );
+    }
+}
+
+/// Everything needed to reproduce a run: the seed it was generated from, the character
+/// flown, the game mode, the level, the tuning and the simulation ticks the bird flapped on.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Replay {
+    pub seed: u64,
+    /// Empty in version 1 replays, which were all flown with the default character.
+    pub character: String,
+    /// Endless in replays before version 3.
+    pub mode: GameMode,
+    /// Path of the level flown, if any. `None` in replays before version 4.
+    pub level: Option<String>,
+    /// [`Tuning::fingerprint`] of the run. 0 in replays before version 4, which play with
+    /// whatever tuning is loaded.
+    pub tuning: u64,
+    pub flaps: Vec<u64>,
+}
+
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("could not access replay file: {0}")]
+    Io(#[from] io::Error),
+    #[error("not a replay file")]
+    BadMagic,
+    #[error("replay version {0} is not supported")]
+    UnsupportedVersion(u8),
+    #[error("replay file is truncated")]
+    Truncated,
+    #[error("replay character name is not valid UTF-8")]
+    BadCharacter,
+    #[error("replay game mode {0} is unknown")]
+    BadGameMode(u8),
+    #[error("replay level path is not valid UTF-8")]
+    BadLevel,
+    #[error("replay number does not fit in 64 bits")]
+    Overflow,
+}
+
+impl Replay {
+    pub fn read(path: &Path) -> Result<Self, ReplayError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), ReplayError> {
+        Ok(fs::write(path, self.to_bytes())?)
+    }
+
+    /// Magic, version and seed, the character name's length and bytes, the game mode's byte,
+    /// the level path's length and bytes (empty for none), the tuning fingerprint, then the
+    /// flap count and the gaps between flap ticks. Lengths, counts and gaps are LEB128
+    /// varints, which keeps a typical run to a few bytes per flap.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(16 + self.flaps.len() * 2);
+        bytes.extend_from_slice(REPLAY_MAGIC);
+        bytes.push(REPLAY_VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        write_varint(&mut bytes, self.character.len() as u64);
+        bytes.extend_from_slice(self.character.as_bytes());
+        bytes.push(self.mode.index());
+        let level = self.level.as_deref().unwrap_or_default();
+        write_varint(&mut bytes, level.len() as u64);
+        bytes.extend_from_slice(level.as_bytes());
+        bytes.extend_from_slice(&self.tuning.to_le_bytes());
+        write_varint(&mut bytes, self.flaps.len() as u64);
+        let mut previous_tick = 0;
+        for &tick in &self.flaps {
+            write_varint(&mut bytes, tick - previous_tick);
+            previous_tick = tick;
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let Some(rest) = bytes.strip_prefix(REPLAY_MAGIC) else {
+            return Err(ReplayError::BadMagic);
+        };
+        let (&version, rest) = rest.split_first().ok_or(ReplayError::Truncated)?;
+        if version == 0 || version > REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        if rest.len() < 8 {
+            return Err(ReplayError::Truncated);
+        }
+        let (seed, mut rest) = rest.split_at(8);
+        let seed = u64::from_le_bytes(seed.try_into().unwrap());
+
+        let mut character = String::new();
+        if version >= 2 {
+            character = read_string(&mut rest, ReplayError::BadCharacter)?;
+        }
+
+        let mut mode = GameMode::Endless;
+        if version >= 3 {
+            let (&index, tail) = rest.split_first().ok_or(ReplayError::Truncated)?;
+            mode = GameMode::from_index(index).ok_or(ReplayError::BadGameMode(index))?;
+            rest = tail;
+        }
+
+        let mut level = None;
+        let mut tuning = 0;
+        if version >= 4 {
+            let path = read_string(&mut rest, ReplayError::BadLevel)?;
+            level = Some(path).filter(|path| !path.is_empty());
+            if rest.len() < 8 {
+                return Err(ReplayError::Truncated);
+            }
+            let (fingerprint, tail) = rest.split_at(8);
+            tuning = u64::from_le_bytes(fingerprint.try_into().unwrap());
+            rest = tail;
+        }
+
+        let count = read_varint(&mut rest)?;
+        let mut flaps = Vec::new();
+        let mut tick: u64 = 0;
+        for _ in 0..count {
+            tick = tick
+                .checked_add(read_varint(&mut rest)?)
+                .ok_or(ReplayError::Overflow)?;
+            flaps.push(tick);
+        }
+        Ok(Self {
+            seed,
+            character,
+            mode,
+            level,
+            tuning,
+            flaps,
+        })
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, ReplayError> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first().ok_or(ReplayError::Truncated)?;
+        *bytes = rest;
+        // the tenth byte holds only the top bit
+        if shift == 63 && byte > 1 {
+            return Err(ReplayError::Overflow);
+        }
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(ReplayError::Truncated)
+}
+
+/// A length-prefixed string, failing with `invalid` if it isn't UTF-8.
+fn read_string(bytes: &mut &[u8], invalid: ReplayError) -> Result<String, ReplayError> {
+    let len = read_varint(bytes)? as usize;
+    if bytes.len() < len {
+        return Err(ReplayError::Truncated);
+    }
+    let (string, rest) = bytes.split_at(len);
+    *bytes = rest;
+    String::from_utf8(string.to_vec()).map_err(|_| invalid)
+}
+
+/// Records the current run and writes it to `path` when the run ends.
+#[derive(Resource)]
+pub struct ReplayRecorder {
+    path: PathBuf,
+    replay: Replay,
+}
+
+impl ReplayRecorder {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            replay: Replay::default(),
+        }
+    }
+}
+
+/// Drives the bird from a recorded replay instead of the keyboard and mouse.
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    next_flap: usize,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            next_flap: 0,
+        }
+    }
+}
+
+fn start_recording(
+    mut recorder: ResMut<ReplayRecorder>,
+    game_rng: Res<GameRng>,
+    tuning: Res<Tuning>,
+    selected: Res<SelectedCharacter>,
+    mode: Res<GameMode>,
+    current_level: Res<CurrentLevel>,
+) {
+    recorder.replay = Replay {
+        seed: game_rng.seed(),
+        character: tuning.character(&selected.0).name.clone(),
+        mode: *mode,
+        level: current_level.0.clone(),
+        tuning: tuning.fingerprint(),
+        flaps: Vec::new(),
+    };
+}
+
+fn record_flaps(
+    mut flaps: EventReader<Flap>,
+    tick: Res<SimulationTick>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    for _ in flaps.read() {
+        recorder.replay.flaps.push(tick.0);
+    }
+}
+
+fn save_recording(recorder: Res<ReplayRecorder>) {
+    match recorder.replay.write(&recorder.path) {
+        Ok(()) => info!("saved replay to {}", recorder.path.display()),
+        Err(error) => warn!(
+            "could not save replay to {}: {error}",
+            recorder.path.display()
+        ),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_playback(
+    mut commands: Commands,
+    playback: Res<ReplayPlayback>,
+    time: Res<Time>,
+    tuning: Res<Tuning>,
+    tuning_assets: Res<Assets<Tuning>>,
+    mut selected: ResMut<SelectedCharacter>,
+    mut mode: ResMut<GameMode>,
+    mut players: ResMut<PlayerCount>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut app_state: ResMut<NextState<AppState>>,
+) {
+    // the tuning file may still be loading
+    let recorded = playback.replay.tuning;
+    let ready = if recorded == 0 {
+        !tuning_assets.is_empty()
+    } else {
+        tuning.fingerprint() == recorded
+    };
+    if !ready {
+        if time.elapsed_seconds() > TUNING_WAIT_SECS {
+            error!("the replay was recorded with different tuning, so it can't be played");
+            commands.remove_resource::<ReplayPlayback>();
+        }
+        return;
+    }
+    selected.0 = playback.replay.character.clone();
+    *mode = playback.replay.mode;
+    *players = PlayerCount(1);
+    current_level.0 = playback.replay.level.clone();
+    app_state.set(AppState::InGame);
+}
+
+// the rest of the run would play out differently with the new values
+fn stop_playback_on_new_tuning(
+    mut commands: Commands,
+    playback: Res<ReplayPlayback>,
+    tuning: Res<Tuning>,
+) {
+    let recorded = playback.replay.tuning;
+    if recorded != 0 && tuning.fingerprint() != recorded {
+        warn!("tuning changed during playback, handing the bird to the player");
+        commands.remove_resource::<ReplayPlayback>();
+    }
+}
+
+fn play_flaps(
+    tick: Res<SimulationTick>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut flaps: EventWriter<Flap>,
+) {
+    while let Some(&flap_tick) = playback.replay.flaps.get(playback.next_flap) {
+        if flap_tick > tick.0 {
+            break;
+        }
+        flaps.send(Flap(0));
+        playback.next_flap += 1;
+    }
+}
+
+// once the replayed run is over the player gets the controls back
+fn finish_playback(mut commands: Commands) {
+    commands.remove_resource::<ReplayPlayback>();
+}
@@ -0,0 +1,67 @@
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::AppState;
+
+const PIPES_STREAM: u64 = 1;
+const CLOUDS_STREAM: u64 = 2;
+const POWER_UPS_STREAM: u64 = 3;
+const COINS_STREAM: u64 = 4;
+const OBSTACLES_STREAM: u64 = 5;
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RngSeed>()
+            .insert_resource(GameRng::new(rand::thread_rng().gen()))
+            .add_systems(OnEnter(AppState::InGame), reseed);
+    }
+}
+
+/// Seed every run starts from. Without one, each run picks a fresh random seed.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct RngSeed(pub Option<u64>);
+
+/// Random number generators for everything procedural in the game.
+///
+/// Each subsystem draws from its own stream of the same seed, so adding draws to one
+/// of them never shifts what the others generate.
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    pub pipes: ChaCha8Rng,
+    pub clouds: ChaCha8Rng,
+    pub power_ups: ChaCha8Rng,
+    pub coins: ChaCha8Rng,
+    pub obstacles: ChaCha8Rng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pipes: stream(seed, PIPES_STREAM),
+            clouds: stream(seed, CLOUDS_STREAM),
+            power_ups: stream(seed, POWER_UPS_STREAM),
+            coins: stream(seed, COINS_STREAM),
+            obstacles: stream(seed, OBSTACLES_STREAM),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+fn stream(seed: u64, stream: u64) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(stream);
+    rng
+}
+
+pub fn reseed(mut game_rng: ResMut<GameRng>, seed: Res<RngSeed>) {
+    let seed = seed.0.unwrap_or_else(|| rand::thread_rng().gen());
+    *game_rng = GameRng::new(seed);
+}
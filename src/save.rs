@@ -0,0 +1,156 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, path::PathBuf};
+
+use crate::coins::Coins;
+use crate::game_modes::GameMode;
+use crate::levels::{LevelResult, LevelRun};
+use crate::AppState;
+use crate::RunTime;
+use crate::Score;
+
+// 2: added coins
+// 3: added level stars
+// 4: best scores by game mode
+const SAVE_VERSION: u32 = 4;
+pub const SAVE_DIRECTORY: &str = "bevy-flappy";
+const SAVE_FILE_NAME: &str = "save.ron";
+
+/// Keeps [`PlayerStats`] in a save file under the user's data directory.
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SavePath(
+            dirs::data_dir().map(|dir| dir.join(SAVE_DIRECTORY).join(SAVE_FILE_NAME)),
+        ))
+        .init_resource::<PlayerStats>()
+        .add_systems(PreStartup, load_save)
+        .add_systems(OnExit(AppState::InGame), (record_run, write_save).chain());
+    }
+}
+
+/// Where the save file lives. `None` when the platform has no data directory, in
+/// which case nothing is persisted.
+#[derive(Resource)]
+pub struct SavePath(pub Option<PathBuf>);
+
+/// Totals across every run the player has played.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct PlayerStats {
+    /// Highest score in each game mode played.
+    pub best_scores: BTreeMap<GameMode, u32>,
+    /// The endless best in saves before version 4, moved into `best_scores` on load.
+    #[serde(skip_serializing)]
+    best_score: u32,
+    pub total_runs: u32,
+    pub total_pipes_passed: u64,
+    pub total_play_time_secs: f64,
+    /// Coins collected and not spent yet.
+    pub coins: u64,
+    /// Most stars earned on each level finished, by level file path.
+    pub level_stars: BTreeMap<String, u8>,
+}
+
+impl PlayerStats {
+    pub fn best_score(&self, mode: GameMode) -> u32 {
+        self.best_scores.get(&mode).copied().unwrap_or(0)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SaveFile {
+    version: u32,
+    #[serde(default)]
+    stats: PlayerStats,
+}
+
+fn read_save(path: &PathBuf) -> Result<Option<PlayerStats>, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error.to_string()),
+    };
+    let save: SaveFile = ron::from_str(&contents).map_err(|error| error.to_string())?;
+    if save.version > SAVE_VERSION {
+        return Err(format!(
+            "save version {} is newer than the supported version {SAVE_VERSION}",
+            save.version
+        ));
+    }
+    let mut stats = save.stats;
+    if stats.best_score > 0 {
+        let best = stats.best_scores.entry(GameMode::Endless).or_default();
+        *best = (*best).max(stats.best_score);
+        stats.best_score = 0;
+    }
+    Ok(Some(stats))
+}
+
+fn write_save_file(path: &PathBuf, stats: &PlayerStats) -> Result<(), String> {
+    let save = SaveFile {
+        version: SAVE_VERSION,
+        stats: stats.clone(),
+    };
+    let contents = ron::ser::to_string_pretty(&save, ron::ser::PrettyConfig::default())
+        .map_err(|error| error.to_string())?;
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory).map_err(|error| error.to_string())?;
+    }
+    // write next to the save and swap it in, so a crash mid-write can't lose it
+    let temporary_path = path.with_extension("ron.tmp");
+    fs::write(&temporary_path, contents).map_err(|error| error.to_string())?;
+    fs::rename(&temporary_path, path).map_err(|error| error.to_string())
+}
+
+fn load_save(mut save_path: ResMut<SavePath>, mut stats: ResMut<PlayerStats>) {
+    let Some(path) = &save_path.0 else {
+        return;
+    };
+    match read_save(path) {
+        Ok(Some(loaded)) => *stats = loaded,
+        Ok(None) => {}
+        Err(error) => {
+            // leave the file alone rather than overwrite it with a fresh save
+            warn!(
+                "could not load save file {}, progress will not be saved: {error}",
+                path.display()
+            );
+            save_path.0 = None;
+        }
+    }
+}
+
+fn record_run(
+    score: Res<Score>,
+    mode: Res<GameMode>,
+    coins: Res<Coins>,
+    run_time: Res<RunTime>,
+    level_run: Option<Res<LevelRun>>,
+    level_result: Option<Res<LevelResult>>,
+    mut stats: ResMut<PlayerStats>,
+) {
+    // a level's pipes aren't comparable with any game mode's
+    if level_run.is_none() {
+        let best = stats.best_scores.entry(*mode).or_default();
+        *best = (*best).max(score.0);
+    }
+    if let Some(result) = level_result {
+        let stars = stats.level_stars.entry(result.path.clone()).or_default();
+        *stars = (*stars).max(result.stars);
+    }
+    stats.total_runs += 1;
+    stats.total_pipes_passed += u64::from(score.0);
+    stats.total_play_time_secs += run_time.0.elapsed_secs_f64();
+    stats.coins += u64::from(coins.0);
+}
+
+fn write_save(save_path: Res<SavePath>, stats: Res<PlayerStats>) {
+    let Some(path) = &save_path.0 else {
+        return;
+    };
+    if let Err(error) = write_save_file(path, &stats) {
+        warn!("could not write save file {}: {error}", path.display());
+    }
+}
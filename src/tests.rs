@@ -0,0 +1,1283 @@
+use bevy::{
+    asset::LoadState,
+    input::{keyboard::KeyboardInput, mouse::MouseButtonInput, ButtonState},
+    prelude::*,
+    time::TimeUpdateStrategy,
+};
+use bevy_rapier2d::prelude::*;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+    time::Duration,
+};
+
+use crate::autopilot::Autopilot;
+use crate::character_select::CharacterSelectPlugin;
+use crate::characters::CharacterDefinition;
+use crate::coins::{Coin, Coins};
+use crate::difficulty::{DifficultyCurve, DifficultyLevel};
+use crate::env::{Action, Env};
+use crate::flappy::{Flappy, Invulnerable};
+use crate::game_modes::GameMode;
+use crate::game_over::GameOverPlugin;
+use crate::ghost::{GhostBird, GhostPlugin, Ghosts, GhostsPath};
+use crate::headless;
+use crate::level_select::LevelSelectPlugin;
+use crate::levels::{CurrentLevel, LevelResult, BUILT_IN_LEVELS};
+use crate::lives::Lives;
+use crate::main_menu::MainMenuPlugin;
+use crate::obstacles::{Obstacle, ObstacleBody};
+use crate::pause::PauseState;
+use crate::pipe_behaviours::PipeBehaviour;
+use crate::pipes::{pipe_offset, GapSensor, PipeBottom, PipeGroup, PipeTop, MIN_GAP_SIZE};
+use crate::players::{PlayerCount, Scoreboard};
+use crate::playfield::Playfield;
+use crate::power_ups::{ActivePowerUps, PowerUpKind};
+use crate::replay::{Replay, ReplayError, ReplayPlayback, ReplayRecorder};
+use crate::rng::RngSeed;
+use crate::save::{PlayerStats, SavePath, SavePlugin};
+use crate::training::{self, Genome, Training};
+use crate::tuning::{ObstacleSpawn, PipeBehaviourSpawn, Tuning, TuningHandle, TuningLoaderError};
+use crate::AppState;
+use crate::Score;
+use crate::SimulationTick;
+use crate::SIMULATION_HZ;
+
+const TEST_SEED: u64 = 7;
+// under `assets/`, with values `Tuning::problems` rejects
+const INVALID_TUNING_PATH: &str = "tests/invalid.tuning.ron";
+
+// enough for several pipes to cross the whole playfield
+const MAX_FRAMES: usize = 60 * 60;
+
+// a pipe this far left of the bird has been passed
+const PIPE_PASSED_X: f32 = -200.0;
+
+/// A file name in the temporary directory, unique to this test run.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("bevy-flappy-{}-{name}", std::process::id()))
+}
+
+/// A headless game with the menus, advancing exactly one simulation step per frame.
+struct TestGame {
+    app: App,
+}
+
+impl TestGame {
+    fn new() -> Self {
+        Self::with_seed(TEST_SEED)
+    }
+
+    fn with_seed(seed: u64) -> Self {
+        Self::with_save(seed, None)
+    }
+
+    /// A game that loads and writes its save file at `save_path`.
+    fn with_save(seed: u64, save_path: Option<PathBuf>) -> Self {
+        let mut app = headless::app(Playfield::default());
+        app.init_asset::<Font>()
+            .add_plugins((
+                MainMenuPlugin,
+                CharacterSelectPlugin,
+                LevelSelectPlugin,
+                GameOverPlugin,
+                SavePlugin,
+                GhostPlugin,
+            ))
+            .insert_resource(SavePath(save_path))
+            .insert_resource(GhostsPath(None))
+            .insert_resource(RngSeed(Some(seed)))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+                1.0 / SIMULATION_HZ,
+            )));
+        let mut game = Self { app };
+        game.step(1);
+        game
+    }
+
+    fn step(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.app.update();
+        }
+    }
+
+    /// Steps until `done` holds, giving up after [`MAX_FRAMES`].
+    fn run_until(&mut self, mut done: impl FnMut(&mut Self) -> bool) -> bool {
+        for _ in 0..MAX_FRAMES {
+            if done(self) {
+                return true;
+            }
+            self.step(1);
+        }
+        false
+    }
+
+    /// Presses and releases `key_code`, one frame each.
+    fn tap_key(&mut self, key_code: KeyCode) {
+        for state in [ButtonState::Pressed, ButtonState::Released] {
+            self.app.world.send_event(KeyboardInput {
+                scan_code: 0,
+                key_code: Some(key_code),
+                state,
+                window: Entity::PLACEHOLDER,
+            });
+            self.step(1);
+        }
+    }
+
+    /// Presses and releases `button`, one frame each.
+    fn click(&mut self, button: MouseButton) {
+        for state in [ButtonState::Pressed, ButtonState::Released] {
+            self.app.world.send_event(MouseButtonInput {
+                button,
+                state,
+                window: Entity::PLACEHOLDER,
+            });
+            self.step(1);
+        }
+    }
+
+    /// Goes from the main menu through character select with the default character.
+    /// Changes the tuning once the tuning file has been applied, so the file doesn't undo it.
+    fn tune(&mut self, change: impl FnOnce(&mut Tuning)) {
+        assert!(self.run_until(|game| !game.app.world.resource::<Assets<Tuning>>().is_empty()));
+        self.step(2);
+        change(&mut self.app.world.resource_mut::<Tuning>());
+    }
+
+    fn start_run(&mut self) {
+        self.tap_key(KeyCode::Space);
+        assert_eq!(self.state(), AppState::CharacterSelect);
+        self.tap_key(KeyCode::Space);
+        assert_eq!(self.state(), AppState::InGame);
+    }
+
+    fn state(&self) -> AppState {
+        *self.app.world.resource::<State<AppState>>().get()
+    }
+
+    fn pause_state(&self) -> PauseState {
+        *self.app.world.resource::<State<PauseState>>().get()
+    }
+
+    fn score(&self) -> u32 {
+        self.app.world.resource::<Score>().0
+    }
+
+    fn pipes(&mut self) -> Vec<(Entity, Vec3)> {
+        let world = &mut self.app.world;
+        world
+            .query_filtered::<(Entity, &Transform), With<PipeGroup>>()
+            .iter(world)
+            .map(|(entity, transform)| (entity, transform.translation))
+            .collect()
+    }
+
+    /// Height and gap size of the first pipe pair the bird hasn't passed yet.
+    fn next_pipe(&mut self) -> Option<(f32, f32)> {
+        let world = &mut self.app.world;
+        world
+            .query::<(&Transform, &PipeGroup)>()
+            .iter(world)
+            .filter(|(transform, _)| transform.translation.x > PIPE_PASSED_X)
+            .min_by(|(a, _), (b, _)| a.translation.x.total_cmp(&b.translation.x))
+            .map(|(transform, pipe_group)| (transform.translation.y, pipe_group.gap_size))
+    }
+
+    fn bird(&mut self) -> Option<(Vec3, Vec2)> {
+        let world = &mut self.app.world;
+        world
+            .query_filtered::<(&Transform, &Velocity), With<Flappy>>()
+            .iter(world)
+            .next()
+            .map(|(transform, velocity)| (transform.translation, velocity.linvel))
+    }
+
+    fn bird_character(&mut self) -> Option<CharacterDefinition> {
+        let world = &mut self.app.world;
+        world
+            .query_filtered::<&CharacterDefinition, With<Flappy>>()
+            .iter(world)
+            .next()
+            .cloned()
+    }
+
+    fn bird_is_invulnerable(&mut self) -> bool {
+        let world = &mut self.app.world;
+        world
+            .query_filtered::<(), (With<Flappy>, With<Invulnerable>)>()
+            .iter(world)
+            .next()
+            .is_some()
+    }
+
+    /// Parks the bird `offset` above the centre of the next pipe gap, as if it were
+    /// flying there perfectly.
+    fn hold_bird_at_next_gap(&mut self, offset: f32) {
+        let gap_y = self.next_pipe().map_or(0.0, |(y, _)| y);
+        self.hold_bird_at(gap_y + offset);
+    }
+
+    /// Parks the bird `offset` from the next obstacle that isn't a pipe pair, on the side
+    /// closer to the middle of the playfield.
+    fn hold_bird_beside_next_obstacle(&mut self, offset: f32) {
+        let world = &mut self.app.world;
+        let obstacle_y = world
+            .query_filtered::<&Transform, With<ObstacleBody>>()
+            .iter(world)
+            .filter(|transform| transform.translation.x > PIPE_PASSED_X)
+            .min_by(|a, b| a.translation.x.total_cmp(&b.translation.x))
+            .map_or(0.0, |transform| transform.translation.y);
+        if obstacle_y > 0.0 {
+            self.hold_bird_at(obstacle_y - offset);
+        } else {
+            self.hold_bird_at(obstacle_y + offset);
+        }
+    }
+
+    fn hold_bird_at(&mut self, y: f32) {
+        let world = &mut self.app.world;
+        let mut q_flappy = world
+            .query_filtered::<(&mut Transform, &mut Velocity, &mut GravityScale), With<Flappy>>();
+        for (mut transform, mut velocity, mut gravity_scale) in q_flappy.iter_mut(world) {
+            transform.translation.y = y;
+            transform.rotation = Quat::IDENTITY;
+            *velocity = Velocity::zero();
+            gravity_scale.0 = 0.0;
+        }
+    }
+
+    fn crash_into_next_pipe(&mut self) {
+        assert!(self.run_until(|game| {
+            let offset = game
+                .next_pipe()
+                .map_or(0.0, |(_, gap_size)| gap_size / 2.0 + 200.0);
+            game.hold_bird_at_next_gap(offset);
+            game.state() == AppState::GameOver
+        }));
+    }
+}
+
+#[test]
+fn space_starts_a_run_from_the_menu() {
+    let mut game = TestGame::new();
+    assert_eq!(game.state(), AppState::GameStart);
+    assert!(game.bird().is_none());
+
+    game.start_run();
+    assert!(game.bird().is_some());
+}
+
+#[test]
+fn the_chosen_character_flies_the_run() {
+    let mut game = TestGame::new();
+    game.tap_key(KeyCode::Space);
+    assert_eq!(game.state(), AppState::CharacterSelect);
+    game.tap_key(KeyCode::Right);
+    game.tap_key(KeyCode::Return);
+    assert_eq!(game.state(), AppState::InGame);
+
+    let second = game.app.world.resource::<Tuning>().characters[1].clone();
+    assert_eq!(game.bird_character().unwrap(), second);
+    let world = &mut game.app.world;
+    let gravity_scale = world
+        .query_filtered::<&GravityScale, With<Flappy>>()
+        .single(world)
+        .0;
+    assert_eq!(gravity_scale, second.gravity_scale);
+
+    // the choice sticks for retries
+    game.crash_into_next_pipe();
+    game.tap_key(KeyCode::Space);
+    assert_eq!(game.bird_character().unwrap().name, second.name);
+}
+
+#[test]
+fn clicking_makes_the_bird_flap() {
+    let mut game = TestGame::new();
+    game.start_run();
+    game.step(30);
+    let (_, falling_velocity) = game.bird().unwrap();
+    assert!(falling_velocity.y < 0.0);
+
+    game.click(MouseButton::Left);
+    let (_, flapping_velocity) = game.bird().unwrap();
+    assert!(flapping_velocity.y > 0.0);
+}
+
+#[test]
+fn passing_a_gap_scores() {
+    let mut game = TestGame::new();
+    game.start_run();
+    assert_eq!(game.score(), 0);
+
+    assert!(game.run_until(|game| {
+        game.hold_bird_at_next_gap(0.0);
+        game.score() > 0
+    }));
+    assert_eq!(game.score(), 1);
+    assert_eq!(game.state(), AppState::InGame);
+}
+
+#[test]
+fn taps_and_gaps_count_when_frames_outpace_the_simulation() {
+    let mut game = TestGame::new();
+    game.tune(|tuning| tuning.power_ups.chance = 0.0);
+    game.start_run();
+    // several frames to each simulation step, most of them without one
+    game.app
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1.0 / 144.0,
+        )));
+
+    // taps landing at each point between two simulation steps
+    for phase in 0..6 {
+        game.hold_bird_at(0.0);
+        game.step(phase);
+        game.tap_key(KeyCode::Space);
+        game.step(3);
+        let (_, velocity) = game.bird().unwrap();
+        assert!(velocity.y > 0.0, "tap {phase} didn't flap");
+    }
+
+    let mut passed = HashSet::new();
+    assert!(game.run_until(|game| {
+        game.hold_bird_at_next_gap(0.0);
+        for (entity, translation) in game.pipes() {
+            if translation.x < PIPE_PASSED_X {
+                passed.insert(entity);
+            }
+        }
+        passed.len() >= 3
+    }));
+    assert_eq!(game.score() as usize, passed.len());
+    assert_eq!(game.state(), AppState::InGame);
+}
+
+#[test]
+fn flying_through_coins_collects_them() {
+    let mut game = TestGame::new();
+    game.start_run();
+
+    assert!(game.run_until(|game| {
+        game.hold_bird_at_next_gap(0.0);
+        game.app.world.resource::<Coins>().0 > 0
+    }));
+    assert_eq!(game.state(), AppState::InGame);
+}
+
+#[test]
+fn birds_sharing_a_coin_count_it_once() {
+    let mut game = TestGame::new();
+    game.tune(|tuning| {
+        tuning.coins.chance = 1.0;
+        tuning.coins.risky_chance = 0.0;
+    });
+    game.app.insert_resource(PlayerCount(2));
+    game.start_run();
+
+    // both birds fly down the middle of the gaps, through the same coins
+    let mut seen = HashSet::new();
+    assert!(game.run_until(|game| {
+        game.hold_bird_at_next_gap(0.0);
+        let world = &mut game.app.world;
+        seen.extend(world.query_filtered::<Entity, With<Coin>>().iter(world));
+        game.app.world.resource::<Coins>().0 >= 3
+    }));
+    game.step(2);
+    let world = &mut game.app.world;
+    let left: HashSet<Entity> = world
+        .query_filtered::<Entity, With<Coin>>()
+        .iter(world)
+        .collect();
+    let collected = seen.difference(&left).count() as u32;
+    assert_eq!(game.app.world.resource::<Coins>().0, collected);
+}
+
+#[test]
+fn hitting_a_pipe_ends_the_run() {
+    let mut game = TestGame::new();
+    game.start_run();
+
+    game.crash_into_next_pipe();
+    assert!(game.bird().is_none());
+    assert!(game.pipes().is_empty());
+}
+
+#[test]
+fn the_autopilot_flies_through_gaps() {
+    let mut game = TestGame::new();
+    game.app.insert_resource(Autopilot::default());
+    game.start_run();
+
+    assert!(game.run_until(|game| game.score() >= 3 || game.state() != AppState::InGame));
+    assert_eq!(game.state(), AppState::InGame);
+}
+
+#[test]
+fn obstacles_score_when_passed_and_kill_on_contact() {
+    let mut game = TestGame::new();
+    game.tune(|tuning| {
+        tuning.obstacles = vec![ObstacleSpawn {
+            from_score: 0,
+            chance: 1.0,
+            obstacle: Obstacle::SpikeBall {
+                diameter: 120.0,
+                amplitude: 0.0,
+                period: 1.0,
+            },
+        }];
+    });
+    game.start_run();
+
+    assert!(game.run_until(|game| {
+        game.hold_bird_beside_next_obstacle(300.0);
+        game.score() > 0
+    }));
+    assert_eq!(game.state(), AppState::InGame);
+    assert!(game.pipes().is_empty());
+
+    assert!(game.run_until(|game| {
+        game.hold_bird_beside_next_obstacle(0.0);
+        game.state() == AppState::GameOver
+    }));
+}
+
+#[test]
+fn finishing_a_level_records_its_stars() {
+    let mut game = TestGame::new();
+    game.tap_key(KeyCode::L);
+    assert_eq!(game.state(), AppState::LevelSelect);
+    game.tap_key(KeyCode::Key1);
+    assert_eq!(game.state(), AppState::CharacterSelect);
+    game.tap_key(KeyCode::Space);
+    assert_eq!(game.state(), AppState::InGame);
+
+    assert!(game.run_until(|game| {
+        game.hold_bird_at_next_gap(0.0);
+        game.state() == AppState::GameOver
+    }));
+    let result = game.app.world.resource::<LevelResult>();
+    assert_eq!((result.deaths, result.stars), (0, 3));
+    assert_eq!(result.coins, result.coin_count);
+    let stats = game.app.world.resource::<PlayerStats>();
+    assert_eq!(stats.level_stars.get(BUILT_IN_LEVELS[0]), Some(&3));
+}
+
+#[test]
+fn a_level_that_fails_to_load_returns_to_the_menu() {
+    let mut game = TestGame::new();
+    game.app
+        .insert_resource(CurrentLevel(Some("levels/missing.level.ron".to_string())));
+    game.start_run();
+    assert!(game.run_until(|game| game.state() == AppState::GameStart));
+    assert_eq!(game.app.world.resource::<CurrentLevel>().0, None);
+}
+
+#[test]
+fn falling_to_the_ground_ends_the_run() {
+    let mut game = TestGame::new();
+    game.start_run();
+
+    assert!(game.run_until(|game| game.state() == AppState::GameOver));
+    assert!(game.pipes().is_empty());
+}
+
+#[test]
+fn a_shield_absorbs_one_hit() {
+    let mut game = TestGame::new();
+    game.start_run();
+    game.app
+        .world
+        .resource_mut::<ActivePowerUps>()
+        .activate(PowerUpKind::Shield, Duration::from_secs(60));
+
+    assert!(game.run_until(|game| {
+        let offset = game
+            .next_pipe()
+            .map_or(0.0, |(_, gap_size)| gap_size / 2.0 + 200.0);
+        game.hold_bird_at_next_gap(offset);
+        game.bird_is_invulnerable()
+    }));
+    assert_eq!(game.state(), AppState::InGame);
+    assert!(!game
+        .app
+        .world
+        .resource::<ActivePowerUps>()
+        .is_active(PowerUpKind::Shield));
+
+    game.crash_into_next_pipe();
+}
+
+#[test]
+fn a_shared_shield_saves_every_bird_crashing_together() {
+    let mut game = TestGame::new();
+    game.app.insert_resource(PlayerCount(2));
+    game.start_run();
+    game.app
+        .world
+        .resource_mut::<ActivePowerUps>()
+        .activate(PowerUpKind::Shield, Duration::from_secs(60));
+
+    // both birds fly into the same pipe at once
+    assert!(game.run_until(|game| {
+        let offset = game
+            .next_pipe()
+            .map_or(0.0, |(_, gap_size)| gap_size / 2.0 + 200.0);
+        game.hold_bird_at_next_gap(offset);
+        game.bird_is_invulnerable()
+    }));
+    let world = &mut game.app.world;
+    let shielded = world
+        .query_filtered::<(), (With<Flappy>, With<Invulnerable>)>()
+        .iter(world)
+        .count();
+    assert_eq!(shielded, 2);
+    assert_eq!(game.state(), AppState::InGame);
+    assert_eq!(game.app.world.resource::<Scoreboard>().flying, [true, true]);
+    assert!(!game
+        .app
+        .world
+        .resource::<ActivePowerUps>()
+        .is_active(PowerUpKind::Shield));
+}
+
+#[test]
+fn a_crash_with_lives_left_respawns_the_bird() {
+    let mut game = TestGame::new();
+    game.start_run();
+    game.app.world.resource_mut::<Lives>().remaining = 2;
+
+    assert!(game.run_until(|game| {
+        if game.app.world.resource::<Lives>().remaining < 2 {
+            return true;
+        }
+        let offset = game
+            .next_pipe()
+            .map_or(0.0, |(_, gap_size)| gap_size / 2.0 + 200.0);
+        game.hold_bird_at_next_gap(offset);
+        false
+    }));
+    assert_eq!(game.state(), AppState::InGame);
+    // respawned in the middle, then fell for one step
+    let (position, _) = game.bird().unwrap();
+    assert!(position.y.abs() < game.bird_character().unwrap().sprite_size / 2.0);
+    assert!(game.bird_is_invulnerable());
+
+    game.crash_into_next_pipe();
+    assert_eq!(game.app.world.resource::<Lives>().remaining, 0);
+}
+
+#[test]
+fn timed_runs_end_when_the_clock_runs_out() {
+    let mut game = TestGame::new();
+    game.tune(|tuning| tuning.modes.timed_secs = 5.0);
+    game.tap_key(KeyCode::M);
+    assert_eq!(*game.app.world.resource::<GameMode>(), GameMode::Timed);
+    game.start_run();
+
+    assert!(game.run_until(|game| {
+        game.hold_bird_at_next_gap(0.0);
+        game.state() == AppState::GameOver
+    }));
+    assert_eq!(game.app.world.resource::<SimulationTick>().0, 5 * 60);
+    let stats = game.app.world.resource::<PlayerStats>();
+    assert!(stats.best_scores.contains_key(&GameMode::Timed));
+    assert!(!stats.best_scores.contains_key(&GameMode::Endless));
+}
+
+#[test]
+fn zen_runs_survive_crashes() {
+    let mut game = TestGame::new();
+    game.app.insert_resource(GameMode::Zen);
+    game.start_run();
+
+    assert!(game.run_until(|game| {
+        let offset = game
+            .next_pipe()
+            .map_or(0.0, |(_, gap_size)| gap_size / 2.0 + 200.0);
+        game.hold_bird_at_next_gap(offset);
+        game.bird_is_invulnerable()
+    }));
+    assert_eq!(game.state(), AppState::InGame);
+    assert!(game.bird().is_some());
+}
+
+#[test]
+fn the_best_run_flies_again_as_a_ghost() {
+    let mut game = TestGame::new();
+    game.start_run();
+    assert!(game.run_until(|game| {
+        game.hold_bird_at_next_gap(0.0);
+        game.score() > 0
+    }));
+    game.crash_into_next_pipe();
+    let ghost = game
+        .app
+        .world
+        .resource::<Ghosts>()
+        .get(GameMode::Endless, TEST_SEED)
+        .cloned()
+        .unwrap();
+    assert_eq!(ghost.score, game.score());
+
+    game.tap_key(KeyCode::Space);
+    assert_eq!(game.state(), AppState::InGame);
+    game.step(30);
+    let tick = game.app.world.resource::<SimulationTick>().0;
+    let world = &mut game.app.world;
+    let ghost_height = world
+        .query_filtered::<&Transform, With<GhostBird>>()
+        .single(world)
+        .translation
+        .y;
+    assert_eq!(ghost_height, ghost.path[tick as usize - 1].0);
+}
+
+#[test]
+fn runs_that_score_nothing_leave_no_ghost() {
+    let mut game = TestGame::new();
+    game.start_run();
+    game.crash_into_next_pipe();
+    assert_eq!(game.score(), 0);
+    assert!(game.app.world.resource::<Ghosts>().0.is_empty());
+}
+
+#[test]
+fn the_last_bird_flying_wins_a_multiplayer_run() {
+    let mut game = TestGame::new();
+    game.app.insert_resource(PlayerCount(2));
+    game.start_run();
+    let world = &mut game.app.world;
+    let tints: Vec<Color> = world
+        .query::<(&Flappy, &Sprite)>()
+        .iter(world)
+        .map(|(_, sprite)| sprite.color)
+        .collect();
+    assert_eq!(tints.len(), 2);
+    assert_ne!(tints[0], tints[1]);
+
+    game.step(30);
+    game.tap_key(KeyCode::Return);
+    let world = &mut game.app.world;
+    for (flappy, velocity) in world.query::<(&Flappy, &Velocity)>().iter(world) {
+        assert_eq!(velocity.linvel.y > 0.0, flappy.0 == 1);
+    }
+
+    // the first bird falls to the ground while the second holds at the gaps
+    assert!(game.run_until(|game| {
+        let gap_y = game.next_pipe().map_or(0.0, |(y, _)| y);
+        let world = &mut game.app.world;
+        let mut q_flappy = world.query::<(&Flappy, &mut Transform, &mut Velocity)>();
+        for (flappy, mut transform, mut velocity) in q_flappy.iter_mut(world) {
+            if flappy.0 == 1 {
+                transform.translation.y = gap_y;
+                *velocity = Velocity::zero();
+            }
+        }
+        game.state() == AppState::GameOver
+    }));
+    let scoreboard = game.app.world.resource::<Scoreboard>();
+    assert_eq!(scoreboard.flying, [false, true]);
+    assert_eq!(scoreboard.winner(false), Some(1));
+}
+
+/// Gives every pipe pair `behaviour` and holds the bird at the gaps until the first pair is
+/// passed, checking the gap sensor fills the gap all along. Returns the first pair's height
+/// and gap size at each step.
+fn fly_past_pipe_with(behaviour: PipeBehaviour) -> Vec<(f32, f32)> {
+    let mut game = TestGame::new();
+    game.tune(|tuning| {
+        tuning.pipes.behaviours = vec![PipeBehaviourSpawn {
+            from_score: 0,
+            chance: 1.0,
+            behaviour,
+        }];
+        tuning.obstacles.clear();
+        tuning.power_ups.chance = 0.0;
+    });
+    game.start_run();
+    assert!(game.run_until(|game| {
+        game.hold_bird_at_next_gap(0.0);
+        !game.pipes().is_empty()
+    }));
+    let (first_pipe, _) = game.pipes()[0];
+
+    let mut samples = Vec::new();
+    assert!(game.run_until(|game| {
+        game.hold_bird_at_next_gap(0.0);
+        let world = &mut game.app.world;
+        let Ok((transform, pipe_group, children)) = world
+            .query::<(&Transform, &PipeGroup, &Children)>()
+            .get(world, first_pipe)
+        else {
+            return true;
+        };
+        if transform.translation.x < PIPE_PASSED_X {
+            return true;
+        }
+        let (y, gap_size) = (transform.translation.y, pipe_group.gap_size);
+        let children: Vec<Entity> = children.iter().copied().collect();
+        samples.push((y, gap_size));
+
+        let mut edges = Vec::new();
+        for child in children {
+            if let Ok(top) = world
+                .query_filtered::<&Transform, With<PipeTop>>()
+                .get(world, child)
+            {
+                edges.push(("top", top.translation.y - pipe_offset(0.0)));
+            }
+            if let Ok(bottom) = world
+                .query_filtered::<&Transform, With<PipeBottom>>()
+                .get(world, child)
+            {
+                edges.push(("bottom", bottom.translation.y + pipe_offset(0.0)));
+            }
+            if let Ok((sensor, sprite)) = world
+                .query_filtered::<(&Transform, &Sprite), With<GapSensor>>()
+                .get(world, child)
+            {
+                let half_height = sprite.custom_size.unwrap().y / 2.0;
+                edges.push(("sensor top", sensor.translation.y + half_height));
+                edges.push(("sensor bottom", sensor.translation.y - half_height));
+            }
+        }
+        for (edge, at) in edges {
+            let expected = if edge.ends_with("top") {
+                gap_size / 2.0
+            } else {
+                -gap_size / 2.0
+            };
+            assert!(
+                (at - expected).abs() < 0.01,
+                "{edge} at {at}, gap {gap_size}"
+            );
+        }
+        false
+    }));
+    assert_eq!(game.state(), AppState::InGame);
+    samples
+}
+
+#[test]
+fn oscillating_pipes_drift_around_their_spawn_height() {
+    let samples = fly_past_pipe_with(PipeBehaviour::Oscillate {
+        amplitude: 150.0,
+        period: 3.0,
+    });
+    let (spawn_y, spawn_gap) = samples[0];
+    let lowest = samples.iter().map(|(y, _)| *y).fold(f32::MAX, f32::min);
+    let highest = samples.iter().map(|(y, _)| *y).fold(f32::MIN, f32::max);
+    assert!(highest - spawn_y > 140.0 && highest - spawn_y < 151.0);
+    assert!(spawn_y - lowest > 140.0 && spawn_y - lowest < 151.0);
+    assert!(samples.iter().all(|(_, gap_size)| *gap_size == spawn_gap));
+}
+
+#[test]
+fn opening_and_closing_pipes_resize_their_gap() {
+    let samples = fly_past_pipe_with(PipeBehaviour::OpenClose {
+        closes_by: 150.0,
+        period: 2.5,
+    });
+    let (spawn_y, widest) = samples[0];
+    let narrowest = samples.iter().map(|(_, gap)| *gap).fold(f32::MAX, f32::min);
+    let expected = f32::max(widest - 150.0, MIN_GAP_SIZE);
+    assert!(narrowest >= expected - 0.01 && narrowest < expected + 5.0);
+    assert!(samples.iter().all(|(_, gap_size)| *gap_size <= widest));
+    assert!(samples.iter().all(|(y, _)| *y == spawn_y));
+}
+
+#[test]
+fn shifting_pipes_move_towards_the_middle_once() {
+    let samples = fly_past_pipe_with(PipeBehaviour::ShiftOnApproach {
+        offset: 200.0,
+        distance: 800.0,
+        speed: 400.0,
+    });
+    let (spawn_y, spawn_gap) = samples[0];
+    let (end_y, _) = *samples.last().unwrap();
+    assert!(((spawn_y - end_y).abs() - 200.0).abs() < 0.5);
+    assert!(end_y.abs() < spawn_y.abs() || spawn_y.signum() != end_y.signum());
+    // it moves one way only, then holds
+    let moves: Vec<f32> = samples
+        .windows(2)
+        .map(|pair| pair[1].0 - pair[0].0)
+        .filter(|step| *step != 0.0)
+        .collect();
+    assert!(moves.iter().all(|step| step.signum() == moves[0].signum()));
+    assert!(samples.iter().all(|(_, gap_size)| *gap_size == spawn_gap));
+}
+
+#[test]
+fn gaps_stay_within_safe_bounds_at_the_hardest_difficulty() {
+    let tallest = Playfield::default().height * 2.0 - 40.0;
+    for (hard_gap_size, expected) in [(50.0, MIN_GAP_SIZE), (5000.0, tallest)] {
+        let mut game = TestGame::new();
+        game.tune(|tuning| {
+            tuning.difficulty.curve = DifficultyCurve::Table(vec![(0.0, 1.0)]);
+            tuning.difficulty.hard.gap_size = hard_gap_size;
+            tuning.pipes.behaviours.clear();
+            tuning.obstacles.clear();
+        });
+        game.start_run();
+
+        let mut gap_sizes = HashMap::new();
+        assert!(game.run_until(|game| {
+            game.hold_bird_at_next_gap(0.0);
+            let world = &mut game.app.world;
+            for (entity, pipe_group) in world.query::<(Entity, &PipeGroup)>().iter(world) {
+                gap_sizes.insert(entity, pipe_group.gap_size);
+            }
+            gap_sizes.len() >= 3
+        }));
+        for gap_size in gap_sizes.values() {
+            assert_eq!(*gap_size, expected, "hard gap size {hard_gap_size}");
+        }
+    }
+}
+
+#[test]
+fn pipes_are_despawned_past_the_despawn_area() {
+    let mut game = TestGame::new();
+    game.start_run();
+    assert!(game.run_until(|game| {
+        game.hold_bird_at_next_gap(0.0);
+        !game.pipes().is_empty()
+    }));
+    let (first_pipe, _) = game.pipes()[0];
+
+    assert!(game.run_until(|game| {
+        game.hold_bird_at_next_gap(0.0);
+        game.app.world.get_entity(first_pipe).is_none()
+    }));
+    assert_eq!(game.state(), AppState::InGame);
+}
+
+#[test]
+fn escape_pauses_and_resumes_the_run() {
+    let mut game = TestGame::new();
+    game.start_run();
+    game.step(10);
+
+    game.tap_key(KeyCode::Escape);
+    assert_eq!(game.pause_state(), PauseState::Paused);
+    let tick = game.app.world.resource::<SimulationTick>().0;
+    let (position, _) = game.bird().unwrap();
+    game.step(30);
+    assert_eq!(game.app.world.resource::<SimulationTick>().0, tick);
+    assert_eq!(game.bird().unwrap().0, position);
+
+    game.tap_key(KeyCode::Escape);
+    assert_eq!(game.pause_state(), PauseState::Running);
+    game.step(10);
+    assert!(game.app.world.resource::<SimulationTick>().0 > tick);
+}
+
+#[test]
+fn game_over_retries_or_returns_to_the_menu() {
+    let mut game = TestGame::new();
+    game.start_run();
+    assert!(game.run_until(|game| {
+        game.hold_bird_at_next_gap(0.0);
+        game.score() > 0
+    }));
+    game.crash_into_next_pipe();
+    assert_eq!(game.score(), 1);
+
+    game.tap_key(KeyCode::Space);
+    assert_eq!(game.state(), AppState::InGame);
+    assert_eq!(game.score(), 0);
+
+    game.crash_into_next_pipe();
+    game.tap_key(KeyCode::Escape);
+    assert_eq!(game.state(), AppState::GameStart);
+}
+
+#[test]
+fn same_seed_generates_the_same_pipes() {
+    let first_pipe = |seed| {
+        let mut game = TestGame::with_seed(seed);
+        game.start_run();
+        assert!(game.run_until(|game| {
+            game.hold_bird_at_next_gap(0.0);
+            !game.pipes().is_empty()
+        }));
+        game.pipes()[0].1.y
+    };
+
+    assert_eq!(first_pipe(TEST_SEED), first_pipe(TEST_SEED));
+    assert_ne!(first_pipe(TEST_SEED), first_pipe(TEST_SEED + 1));
+}
+
+#[test]
+fn the_environment_plays_the_same_episode_for_a_seed() {
+    let mut env = Env::new();
+    let episode = |env: &mut Env| {
+        let mut observations = vec![env.reset(TEST_SEED)];
+        let mut total_reward = 0.0;
+        for _ in 0..MAX_FRAMES {
+            let observation = observations.last().unwrap();
+            let action = if observation.gaps[0].above > 0.0 && observation.vertical_speed <= 0.0 {
+                Action::Flap
+            } else {
+                Action::Wait
+            };
+            let (observation, reward, done) = env.step(action);
+            observations.push(observation);
+            total_reward += reward;
+            if done {
+                break;
+            }
+        }
+        (observations, total_reward)
+    };
+
+    let (first, first_reward) = episode(&mut env);
+    let (second, second_reward) = episode(&mut env);
+    assert!(first_reward > 0.0);
+    assert_eq!(first_reward, second_reward);
+    assert_eq!(first, second);
+    assert!(first
+        .iter()
+        .any(|observation| observation.gaps[1].size > 0.0));
+}
+
+#[test]
+fn training_evolves_genomes_and_saves_the_best() {
+    let mut app = training::app(Training::new(8, TEST_SEED, 10.0));
+    for _ in 0..MAX_FRAMES * 2 {
+        if app.world.resource::<Training>().generation >= 2 {
+            break;
+        }
+        app.update();
+    }
+    assert_eq!(app.world.query::<&Flappy>().iter(&app.world).count(), 0);
+    let training = app.world.resource::<Training>();
+    assert!(training.generation >= 2);
+
+    let (genome, fitness) = training.best().unwrap();
+    assert!(fitness.ticks > 0);
+    let path = std::env::temp_dir().join(format!("bevy-flappy-genome-{TEST_SEED}.ron"));
+    genome.write(&path).unwrap();
+    assert_eq!(&Genome::read(&path).unwrap(), genome);
+    std::fs::remove_file(path).unwrap();
+}
+
+fn sample_replay() -> Replay {
+    Replay {
+        seed: 42,
+        character: "Robin".to_string(),
+        mode: GameMode::Timed,
+        level: Some(BUILT_IN_LEVELS[0].to_string()),
+        tuning: 0xdead_beef,
+        flaps: vec![3, 10, 10, 500, 70_000],
+    }
+}
+
+#[test]
+fn replays_round_trip_through_bytes() {
+    let replay = sample_replay();
+    assert_eq!(Replay::from_bytes(&replay.to_bytes()).unwrap(), replay);
+
+    let endless = Replay {
+        level: None,
+        mode: GameMode::Endless,
+        ..sample_replay()
+    };
+    assert_eq!(Replay::from_bytes(&endless.to_bytes()).unwrap(), endless);
+}
+
+#[test]
+fn older_replay_versions_still_load() {
+    // version 1: the seed, then the flaps
+    let mut bytes = b"FLRP\x01".to_vec();
+    bytes.extend_from_slice(&42u64.to_le_bytes());
+    bytes.extend_from_slice(&[2, 3, 7]);
+    let replay = Replay::from_bytes(&bytes).unwrap();
+    assert_eq!(
+        replay,
+        Replay {
+            seed: 42,
+            flaps: vec![3, 10],
+            ..default()
+        }
+    );
+
+    // version 3: the character and the game mode in between
+    let mut bytes = b"FLRP\x03".to_vec();
+    bytes.extend_from_slice(&42u64.to_le_bytes());
+    bytes.push(5);
+    bytes.extend_from_slice(b"Robin");
+    bytes.push(GameMode::Zen.index());
+    bytes.extend_from_slice(&[1, 4]);
+    let replay = Replay::from_bytes(&bytes).unwrap();
+    assert_eq!(
+        replay,
+        Replay {
+            seed: 42,
+            character: "Robin".to_string(),
+            mode: GameMode::Zen,
+            level: None,
+            tuning: 0,
+            flaps: vec![4],
+        }
+    );
+}
+
+#[test]
+fn truncated_replays_are_rejected() {
+    let bytes = sample_replay().to_bytes();
+    for len in 4..bytes.len() {
+        assert!(
+            matches!(
+                Replay::from_bytes(&bytes[..len]),
+                Err(ReplayError::Truncated)
+            ),
+            "{len} bytes"
+        );
+    }
+}
+
+#[test]
+fn malformed_replays_are_rejected() {
+    let replay = sample_replay();
+    let bytes = replay.to_bytes();
+
+    let mut bad_magic = bytes.clone();
+    bad_magic[..4].copy_from_slice(b"NOPE");
+    assert!(matches!(
+        Replay::from_bytes(&bad_magic),
+        Err(ReplayError::BadMagic)
+    ));
+
+    for version in [0, 99] {
+        let mut bad_version = bytes.clone();
+        bad_version[4] = version;
+        assert!(matches!(
+            Replay::from_bytes(&bad_version),
+            Err(ReplayError::UnsupportedVersion(v)) if v == version
+        ));
+    }
+
+    // magic, version, seed, then the character's length and name
+    let mode_at = 4 + 1 + 8 + 1 + replay.character.len();
+    let mut bad_mode = bytes.clone();
+    bad_mode[mode_at] = 200;
+    assert!(matches!(
+        Replay::from_bytes(&bad_mode),
+        Err(ReplayError::BadGameMode(200))
+    ));
+
+    // two flaps whose ticks add up past u64::MAX
+    let mut overflowing = b"FLRP\x01".to_vec();
+    overflowing.extend_from_slice(&42u64.to_le_bytes());
+    overflowing.push(2);
+    overflowing.extend_from_slice(&[0xff; 9]);
+    overflowing.extend_from_slice(&[0x01, 0x01]);
+    assert!(matches!(
+        Replay::from_bytes(&overflowing),
+        Err(ReplayError::Overflow)
+    ));
+
+    // a tenth varint byte with more than the top bit
+    let mut too_long = b"FLRP\x01".to_vec();
+    too_long.extend_from_slice(&42u64.to_le_bytes());
+    too_long.extend_from_slice(&[0xff; 9]);
+    too_long.push(0x02);
+    assert!(matches!(
+        Replay::from_bytes(&too_long),
+        Err(ReplayError::Overflow)
+    ));
+}
+
+#[test]
+fn a_replay_flies_the_recorded_run_again() {
+    let path = temp_path("run.replay");
+    let mut game = TestGame::new();
+    game.tune(|_| {});
+    game.app
+        .insert_resource(ReplayRecorder::new(path.clone()))
+        .insert_resource(Autopilot::default());
+    game.start_run();
+    assert!(game.run_until(|game| game.score() >= 2));
+    // the bird falls to the ground once the autopilot lets go
+    game.app.world.remove_resource::<Autopilot>();
+    assert!(game.run_until(|game| game.state() == AppState::GameOver));
+    let score = game.score();
+    let replay = Replay::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(!replay.flaps.is_empty());
+
+    let mut game = TestGame::new();
+    game.app.insert_resource(ReplayPlayback::new(replay));
+    assert!(game.run_until(|game| game.state() == AppState::InGame));
+    assert!(game.run_until(|game| game.state() == AppState::GameOver));
+    assert_eq!(game.score(), score);
+}
+
+#[test]
+fn difficulty_curves_rise_from_easiest_to_hardest() {
+    let curves = [
+        (DifficultyCurve::Linear { full_at: 20.0 }, 20.0),
+        (DifficultyCurve::Stepped { every: 5, steps: 4 }, 20.0),
+        (DifficultyCurve::Logarithmic { full_at: 20.0 }, 20.0),
+        (
+            DifficultyCurve::Table(vec![(0.0, 0.0), (10.0, 0.5), (30.0, 1.0)]),
+            30.0,
+        ),
+    ];
+    for (curve, full_at) in curves {
+        assert_eq!(curve.problem(), None, "{curve:?}");
+        assert_eq!(curve.intensity(-5.0), 0.0, "{curve:?}");
+        assert_eq!(curve.intensity(0.0), 0.0, "{curve:?}");
+        assert_eq!(curve.intensity(full_at), 1.0, "{curve:?}");
+        assert_eq!(curve.intensity(full_at * 2.0), 1.0, "{curve:?}");
+        let mut previous = 0.0;
+        for step in 0..=(full_at as usize * 8) {
+            let intensity = curve.intensity(step as f32 / 4.0);
+            assert!(intensity >= previous, "{curve:?} falls at {step}");
+            previous = intensity;
+        }
+    }
+
+    let stepped = DifficultyCurve::Stepped { every: 5, steps: 4 };
+    assert_eq!(stepped.intensity(4.9), 0.0);
+    assert_eq!(stepped.intensity(5.0), 0.25);
+}
+
+#[test]
+fn difficulty_tables_interpolate_and_hold_their_ends() {
+    let table = DifficultyCurve::Table(vec![(10.0, 0.2), (20.0, 0.6)]);
+    assert_eq!(table.intensity(5.0), 0.2);
+    assert_eq!(table.intensity(10.0), 0.2);
+    assert!((table.intensity(15.0) - 0.4).abs() < 1e-6);
+    assert_eq!(table.intensity(20.0), 0.6);
+    assert_eq!(table.intensity(100.0), 0.6);
+}
+
+#[test]
+fn unusable_difficulty_curves_are_reported() {
+    let unusable = [
+        DifficultyCurve::Linear { full_at: 0.0 },
+        DifficultyCurve::Logarithmic { full_at: -1.0 },
+        DifficultyCurve::Stepped { every: 0, steps: 4 },
+        DifficultyCurve::Stepped { every: 5, steps: 0 },
+        DifficultyCurve::Table(Vec::new()),
+        DifficultyCurve::Table(vec![(10.0, 0.5), (5.0, 1.0)]),
+        DifficultyCurve::Table(vec![(5.0, 0.5), (5.0, 1.0)]),
+        DifficultyCurve::Table(vec![(5.0, 1.5)]),
+    ];
+    for curve in unusable {
+        assert!(curve.problem().is_some(), "{curve:?}");
+    }
+}
+
+#[test]
+fn difficulty_levels_blend_between_easy_and_hard() {
+    let easy = DifficultyLevel {
+        speed: -300.0,
+        spawn_interval: 5.0,
+        gap_size: 400.0,
+        vertical_variance: 0.25,
+    };
+    let hard = DifficultyLevel {
+        speed: -600.0,
+        spawn_interval: 2.0,
+        gap_size: 200.0,
+        vertical_variance: 0.75,
+    };
+    assert_eq!(easy.lerp(&hard, 0.0), easy);
+    assert_eq!(easy.lerp(&hard, 1.0), hard);
+    let middle = easy.lerp(&hard, 0.5);
+    assert_eq!(middle.speed, -450.0);
+    assert_eq!(middle.spawn_interval, 3.5);
+    assert_eq!(middle.gap_size, 300.0);
+    assert_eq!(middle.vertical_variance, 0.5);
+}
+
+#[test]
+fn tuning_with_unusable_values_is_rejected() {
+    let error =
+        Tuning::from_ron(b"(pipes: (spawn_first: -1.0), coins: (chance: 2.0))").unwrap_err();
+    let TuningLoaderError::Invalid(problems) = error else {
+        panic!("unexpected error: {error}");
+    };
+    assert_eq!(problems.len(), 2, "{problems:?}");
+
+    let shipped = fs::read("assets/game.tuning.ron").unwrap();
+    assert!(Tuning::from_ron(&shipped).is_ok());
+}
+
+#[test]
+fn an_invalid_tuning_file_keeps_the_last_good_values() {
+    let mut game = TestGame::new();
+    game.tune(|_| {});
+    let good = game.app.world.resource::<Tuning>().fingerprint();
+
+    let handle = game
+        .app
+        .world
+        .resource::<AssetServer>()
+        .load(INVALID_TUNING_PATH);
+    game.app.world.resource_mut::<TuningHandle>().0 = handle.clone();
+    assert!(game.run_until(|game| {
+        let asset_server = game.app.world.resource::<AssetServer>();
+        asset_server.get_load_state(&handle) == Some(LoadState::Failed)
+    }));
+    game.step(2);
+    assert_eq!(game.app.world.resource::<Tuning>().fingerprint(), good);
+}
+
+#[test]
+fn older_saves_load_into_the_current_stats() {
+    let saves = [
+        "(version: 1, stats: (best_score: 12, total_runs: 3, total_pipes_passed: 30))",
+        "(version: 2, stats: (best_score: 12, total_runs: 3, total_pipes_passed: 30, coins: 5))",
+        r#"(version: 3, stats: (best_score: 12, total_runs: 3, total_pipes_passed: 30, coins: 5,
+            level_stars: {"levels/first_flight.level.ron": 2}))"#,
+    ];
+    for (version, contents) in (1..).zip(saves) {
+        let path = temp_path(&format!("v{version}.save.ron"));
+        fs::write(&path, contents).unwrap();
+        let game = TestGame::with_save(TEST_SEED, Some(path.clone()));
+        fs::remove_file(&path).unwrap();
+
+        let stats = game.app.world.resource::<PlayerStats>();
+        assert_eq!(stats.best_score(GameMode::Endless), 12, "version {version}");
+        assert_eq!(stats.total_runs, 3, "version {version}");
+        assert_eq!(stats.total_pipes_passed, 30, "version {version}");
+        assert_eq!(stats.coins, if version >= 2 { 5 } else { 0 });
+        let stars = stats.level_stars.get(BUILT_IN_LEVELS[0]).copied();
+        assert_eq!(stars, (version >= 3).then_some(2));
+    }
+}
+
+#[test]
+fn saves_round_trip_through_the_save_file() {
+    let directory = temp_path("saves");
+    let path = directory.join("save.ron");
+    let mut game = TestGame::with_save(TEST_SEED, Some(path.clone()));
+    game.start_run();
+    assert!(game.run_until(|game| {
+        game.hold_bird_at_next_gap(0.0);
+        game.score() > 0
+    }));
+    game.crash_into_next_pipe();
+    let written = game.app.world.resource::<PlayerStats>().clone();
+    assert_eq!(written.total_runs, 1);
+    // the save is swapped in whole, with nothing left beside it
+    assert!(path.exists());
+    assert!(!path.with_extension("ron.tmp").exists());
+
+    let game = TestGame::with_save(TEST_SEED, Some(path));
+    fs::remove_dir_all(&directory).unwrap();
+    let loaded = game.app.world.resource::<PlayerStats>();
+    assert_eq!(loaded.best_scores, written.best_scores);
+    assert_eq!(loaded.total_runs, written.total_runs);
+    assert_eq!(loaded.total_pipes_passed, written.total_pipes_passed);
+    assert_eq!(loaded.total_play_time_secs, written.total_play_time_secs);
+    assert_eq!(loaded.coins, written.coins);
+    assert_eq!(loaded.level_stars, written.level_stars);
+}
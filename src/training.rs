@@ -0,0 +1,401 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
+use thiserror::Error;
+
+use crate::autopilot;
+use crate::characters::{CharacterDefinition, SelectedCharacter};
+use crate::env::Observation;
+use crate::flappy::{self, Lethal};
+use crate::headless;
+use crate::obstacles::{self, ObstaclePassed};
+use crate::pause::PauseState;
+use crate::pipes::{GapSensor, PipeGroup};
+use crate::playfield::Playfield;
+use crate::rng::{self, RngSeed};
+use crate::tuning::Tuning;
+use crate::AppState;
+use crate::Score;
+use crate::SimulationSet;
+use crate::SimulationTick;
+use crate::SIMULATION_HZ;
+
+// the bird's height and vertical speed, then distance, height and size of the next two gaps
+const INPUTS: usize = 8;
+const HIDDEN: usize = 6;
+// a bias and a weight per input for each hidden neuron, then the same for the output
+const GENOME_LEN: usize = (INPUTS + 1) * HIDDEN + HIDDEN + 1;
+// brings distances and speeds in pixels to around one
+const INPUT_SCALE: f32 = 1.0 / 500.0;
+/// Share of each generation kept unchanged, and bred from, in the next.
+const ELITE_FRACTION: f32 = 0.2;
+/// Chance of each weight of a child changing.
+const MUTATION_RATE: f64 = 0.1;
+/// Largest change a mutation makes to a weight.
+const MUTATION_SIZE: f32 = 0.5;
+
+/// Flies a whole population of [`Genome`]s through the same pipes whenever a [`Training`]
+/// resource is inserted, and breeds the next generation once every bird has crashed.
+///
+/// The player's bird isn't spawned while training.
+pub struct TrainingPlugin;
+
+impl Plugin for TrainingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            (seed_generation.before(rng::reseed), spawn_population)
+                .run_if(resource_exists::<Training>()),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                fly_population.in_set(SimulationSet::Input),
+                (
+                    score_trainees.after(obstacles::score_passed_obstacles),
+                    crash_trainees,
+                    end_generation,
+                )
+                    .chain()
+                    .in_set(SimulationSet::Gameplay),
+            )
+                .run_if(resource_exists::<Training>())
+                .run_if(in_state(AppState::InGame))
+                .run_if(in_state(PauseState::Running)),
+        )
+        .add_systems(OnExit(AppState::InGame), despawn_population);
+    }
+}
+
+/// A headless app that starts training right away, for stepping by hand as fast as it goes.
+pub fn app(training: Training) -> App {
+    let mut app = headless::stepped_app(Playfield::default());
+    app.add_plugins(TrainingPlugin).insert_resource(training);
+    app.world
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::InGame);
+    app
+}
+
+/// The weights of a small neural network that decides when a bird flaps.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Genome {
+    weights: Vec<f32>,
+}
+
+#[derive(Debug, Error)]
+pub enum GenomeError {
+    #[error("could not access genome file: {0}")]
+    Io(#[from] io::Error),
+    #[error("not a genome file: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("could not write genome: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("genome has {0} weights instead of {}", GENOME_LEN)]
+    WrongSize(usize),
+}
+
+impl Genome {
+    pub fn random(rng: &mut impl Rng) -> Self {
+        Self {
+            weights: (0..GENOME_LEN).map(|_| rng.gen_range(-1.0..1.0)).collect(),
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self, GenomeError> {
+        let genome: Self = ron::from_str(&fs::read_to_string(path)?)?;
+        if genome.weights.len() != GENOME_LEN {
+            return Err(GenomeError::WrongSize(genome.weights.len()));
+        }
+        Ok(genome)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), GenomeError> {
+        Ok(fs::write(path, ron::to_string(self)?)?)
+    }
+
+    /// Whether the bird should flap, given what it sees.
+    pub fn flaps(&self, observation: &Observation) -> bool {
+        let [near, far] = observation.gaps;
+        let inputs = [
+            observation.height,
+            observation.vertical_speed,
+            near.distance,
+            near.above,
+            near.size,
+            far.distance,
+            far.above,
+            far.size,
+        ]
+        .map(|input| input * INPUT_SCALE);
+        let (hidden_weights, output_weights) = self.weights.split_at((INPUTS + 1) * HIDDEN);
+        let hidden = hidden_weights
+            .chunks(INPUTS + 1)
+            .map(|neuron| neuron_output(neuron, &inputs).tanh());
+        let (bias, weights) = output_weights.split_last().unwrap();
+        let output = bias
+            + weights
+                .iter()
+                .zip(hidden)
+                .map(|(weight, input)| weight * input)
+                .sum::<f32>();
+        output > 0.0
+    }
+
+    /// Each weight taken from either parent at random.
+    fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        Self {
+            weights: self
+                .weights
+                .iter()
+                .zip(&other.weights)
+                .map(|(a, b)| if rng.gen() { *a } else { *b })
+                .collect(),
+        }
+    }
+
+    fn mutate(&mut self, rng: &mut impl Rng) {
+        for weight in self.weights.iter_mut() {
+            if rng.gen_bool(MUTATION_RATE) {
+                *weight += rng.gen_range(-MUTATION_SIZE..MUTATION_SIZE);
+            }
+        }
+    }
+}
+
+// the neuron's weights end with its bias
+fn neuron_output(neuron: &[f32], inputs: &[f32]) -> f32 {
+    let (bias, weights) = neuron.split_last().unwrap();
+    bias + weights
+        .iter()
+        .zip(inputs)
+        .map(|(weight, input)| weight * input)
+        .sum::<f32>()
+}
+
+/// How well a genome flew: gaps passed, then ticks survived to break ties.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Fitness {
+    pub score: u32,
+    pub ticks: u64,
+}
+
+/// The population being evolved, and the best genome found so far.
+///
+/// Every generation flies pipes from its own seed, so the nets can't learn one layout.
+#[derive(Resource)]
+pub struct Training {
+    /// Generations flown so far.
+    pub generation: u32,
+    population: Vec<Genome>,
+    fitness: Vec<Fitness>,
+    best: Option<(Genome, Fitness)>,
+    seed: u64,
+    rng: ChaCha8Rng,
+    max_ticks: u64,
+}
+
+impl Training {
+    /// A population of random genomes. A generation still flying after `max_secs` is scored
+    /// as it stands.
+    pub fn new(population: usize, seed: u64, max_secs: f64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        Self {
+            generation: 0,
+            population: (0..population).map(|_| Genome::random(&mut rng)).collect(),
+            fitness: vec![Fitness::default(); population],
+            best: None,
+            seed,
+            rng,
+            max_ticks: (max_secs * SIMULATION_HZ).round() as u64,
+        }
+    }
+
+    pub fn best(&self) -> Option<(&Genome, Fitness)> {
+        self.best
+            .as_ref()
+            .map(|(genome, fitness)| (genome, *fitness))
+    }
+
+    /// Replaces the population with the children of its fittest genomes.
+    fn evolve(&mut self) {
+        let mut ranked: Vec<(Fitness, Genome)> = self
+            .fitness
+            .drain(..)
+            .zip(self.population.drain(..))
+            .collect();
+        ranked.sort_by(|(a, _), (b, _)| b.cmp(a));
+        let size = ranked.len();
+        if let Some((fitness, genome)) = ranked.first() {
+            if self.best.as_ref().is_none_or(|(_, best)| fitness > best) {
+                self.best = Some((genome.clone(), *fitness));
+            }
+        }
+
+        let elites = ((size as f32 * ELITE_FRACTION).ceil() as usize).clamp(1, size.max(1));
+        let parents: Vec<Genome> = ranked
+            .into_iter()
+            .take(elites)
+            .map(|(_, genome)| genome)
+            .collect();
+        self.population = parents.clone();
+        while self.population.len() < size {
+            let a = &parents[self.rng.gen_range(0..parents.len())];
+            let b = &parents[self.rng.gen_range(0..parents.len())];
+            let mut child = a.crossover(b, &mut self.rng);
+            child.mutate(&mut self.rng);
+            self.population.push(child);
+        }
+        self.fitness = vec![Fitness::default(); size];
+        self.generation += 1;
+    }
+}
+
+/// A bird flown by the genome at `index` in the population.
+#[derive(Component)]
+struct Trainee {
+    index: usize,
+    score: u32,
+    crashed: bool,
+}
+
+fn seed_generation(training: Res<Training>, mut seed: ResMut<RngSeed>) {
+    seed.0 = Some(training.seed.wrapping_add(u64::from(training.generation)));
+}
+
+fn spawn_population(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    tuning: Res<Tuning>,
+    selected: Res<SelectedCharacter>,
+    training: Res<Training>,
+) {
+    let character = tuning.character(&selected.0);
+    for index in 0..training.population.len() {
+        commands
+            .spawn(flappy::bird_bundle(&asset_server, character))
+            .insert(Trainee {
+                index,
+                score: 0,
+                crashed: false,
+            });
+    }
+}
+
+fn fly_population(
+    training: Res<Training>,
+    mut q_trainees: Query<(
+        &Trainee,
+        &CharacterDefinition,
+        &mut Velocity,
+        &mut ExternalImpulse,
+        &mut Transform,
+    )>,
+    q_gap_sensors: Query<&Parent, With<GapSensor>>,
+    q_pipe_groups: Query<(&Transform, &PipeGroup), Without<Trainee>>,
+) {
+    let gaps = autopilot::gaps(&q_gap_sensors, &q_pipe_groups);
+    for (trainee, character, mut velocity, mut external_impulse, mut transform) in
+        q_trainees.iter_mut()
+    {
+        let observation = Observation::new(&transform, &velocity, character, gaps.iter().copied());
+        if training.population[trainee.index].flaps(&observation) {
+            flappy::flap_bird(
+                character,
+                &mut velocity,
+                &mut external_impulse,
+                &mut transform,
+            );
+        }
+    }
+}
+
+// every trainee counts every gap for itself, and the pipes get harder with the leader's score
+fn score_trainees(
+    mut collision_events: EventReader<CollisionEvent>,
+    mut obstacles_passed: EventReader<ObstaclePassed>,
+    mut q_trainees: Query<&mut Trainee>,
+    q_gap_sensors: Query<(), With<GapSensor>>,
+    mut score: ResMut<Score>,
+) {
+    // obstacles score for every bird still flying, as they all pass them together
+    for _ in obstacles_passed.read() {
+        for mut trainee in q_trainees.iter_mut().filter(|trainee| !trainee.crashed) {
+            trainee.score += 1;
+            score.0 = score.0.max(trainee.score);
+        }
+    }
+    for collision_event in collision_events.read() {
+        let CollisionEvent::Started(entity1, entity2, _) = *collision_event else {
+            continue;
+        };
+        for (trainee, sensor) in [(entity1, entity2), (entity2, entity1)] {
+            if let (Ok(mut trainee), Ok(())) =
+                (q_trainees.get_mut(trainee), q_gap_sensors.get(sensor))
+            {
+                trainee.score += 1;
+                score.0 = score.0.max(trainee.score);
+            }
+        }
+    }
+}
+
+fn crash_trainees(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    tick: Res<SimulationTick>,
+    mut training: ResMut<Training>,
+    mut q_trainees: Query<(Entity, &mut Trainee)>,
+    q_lethal: Query<Entity, With<Lethal>>,
+) {
+    for (entity, mut trainee) in q_trainees.iter_mut() {
+        let hit = q_lethal
+            .iter()
+            .any(|lethal| rapier_context.contact_pair(entity, lethal).is_some());
+        if hit && !trainee.crashed {
+            trainee.crashed = true;
+            training.fitness[trainee.index] = Fitness {
+                score: trainee.score,
+                ticks: tick.0,
+            };
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn end_generation(
+    tick: Res<SimulationTick>,
+    mut training: ResMut<Training>,
+    q_trainees: Query<&Trainee>,
+    mut app_state: ResMut<NextState<AppState>>,
+) {
+    // a frame can run more than one tick before the restart takes effect
+    if app_state.0.is_some() {
+        return;
+    }
+    // crashed trainees are only despawned once the commands apply
+    let flying: Vec<&Trainee> = q_trainees
+        .iter()
+        .filter(|trainee| !trainee.crashed)
+        .collect();
+    if !flying.is_empty() && tick.0 < training.max_ticks {
+        return;
+    }
+    for trainee in flying {
+        training.fitness[trainee.index] = Fitness {
+            score: trainee.score,
+            ticks: tick.0,
+        };
+    }
+    training.evolve();
+    app_state.set(AppState::Restarting);
+}
+
+fn despawn_population(mut commands: Commands, q_trainees: Query<Entity, With<Trainee>>) {
+    for entity in q_trainees.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,621 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    reflect::TypePath,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::bounds::Ceiling;
+use crate::characters::{self, CharacterDefinition};
+use crate::difficulty::{DifficultyCurve, DifficultyLevel};
+use crate::obstacles::Obstacle;
+use crate::pipe_behaviours::PipeBehaviour;
+use crate::power_ups::PowerUpKind;
+
+const TUNING_PATH: &str = "game.tuning.ron";
+
+/// Loads gameplay tuning from `assets/game.tuning.ron` and keeps [`Tuning`] in sync
+/// with it, so edits to the file apply while the game runs.
+pub struct TuningPlugin;
+
+impl Plugin for TuningPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Tuning>()
+            .register_asset_loader(TuningLoader)
+            .init_resource::<Tuning>()
+            .add_systems(Startup, load_tuning)
+            .add_systems(PreUpdate, apply_tuning);
+    }
+}
+
+/// Gameplay values designers can tweak without recompiling.
+///
+/// Every field is optional in the file; missing ones keep the values below.
+#[derive(Asset, Resource, TypePath, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tuning {
+    pub pipes: PipeTuning,
+    /// Obstacles that can spawn instead of a pipe pair.
+    pub obstacles: Vec<ObstacleSpawn>,
+    pub difficulty: DifficultyTuning,
+    pub modes: ModeTuning,
+    pub bounds: BoundsTuning,
+    pub coins: CoinTuning,
+    pub power_ups: PowerUpTuning,
+    pub lives: LivesTuning,
+    pub clouds: CloudTuning,
+    /// Birds to choose from before a run. The first is the default.
+    pub characters: Vec<CharacterDefinition>,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self {
+            pipes: default(),
+            obstacles: vec![
+                ObstacleSpawn {
+                    from_score: 8,
+                    chance: 0.15,
+                    obstacle: Obstacle::RotatingBar {
+                        length: 400.0,
+                        angular_speed: 1.5,
+                    },
+                },
+                ObstacleSpawn {
+                    from_score: 12,
+                    chance: 0.15,
+                    obstacle: Obstacle::SpikeBall {
+                        diameter: 120.0,
+                        amplitude: 150.0,
+                        period: 3.0,
+                    },
+                },
+                ObstacleSpawn {
+                    from_score: 16,
+                    chance: 0.15,
+                    obstacle: Obstacle::EnemyBird {
+                        size: 80.0,
+                        speed: 250.0,
+                        homing: 120.0,
+                    },
+                },
+                ObstacleSpawn {
+                    from_score: 25,
+                    chance: 0.15,
+                    obstacle: Obstacle::Tunnel {
+                        pipes: 3,
+                        gap_size: 350.0,
+                        step: 60.0,
+                    },
+                },
+            ],
+            difficulty: default(),
+            modes: default(),
+            bounds: default(),
+            coins: default(),
+            power_ups: default(),
+            lives: default(),
+            clouds: default(),
+            characters: characters::built_in(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct PipeTuning {
+    pub spawn_first: f32,
+    pub behaviours: Vec<PipeBehaviourSpawn>,
+}
+
+impl Default for PipeTuning {
+    fn default() -> Self {
+        Self {
+            spawn_first: 1.0,
+            behaviours: vec![
+                PipeBehaviourSpawn {
+                    from_score: 10,
+                    chance: 0.3,
+                    behaviour: PipeBehaviour::Oscillate {
+                        amplitude: 150.0,
+                        period: 3.0,
+                    },
+                },
+                PipeBehaviourSpawn {
+                    from_score: 15,
+                    chance: 0.25,
+                    behaviour: PipeBehaviour::OpenClose {
+                        closes_by: 150.0,
+                        period: 2.5,
+                    },
+                },
+                PipeBehaviourSpawn {
+                    from_score: 20,
+                    chance: 0.25,
+                    behaviour: PipeBehaviour::ShiftOnApproach {
+                        offset: 200.0,
+                        distance: 800.0,
+                        speed: 400.0,
+                    },
+                },
+            ],
+        }
+    }
+}
+
+/// Gives newly spawned pipe pairs `behaviour` with probability `chance` once the score
+/// reaches `from_score`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PipeBehaviourSpawn {
+    pub from_score: u32,
+    pub chance: f64,
+    pub behaviour: PipeBehaviour,
+}
+
+/// Spawns `obstacle` instead of a pipe pair with probability `chance` once the score
+/// reaches `from_score`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ObstacleSpawn {
+    pub from_score: u32,
+    pub chance: f64,
+    pub obstacle: Obstacle,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct DifficultyTuning {
+    /// Curve for runs whose game mode doesn't pick one.
+    pub curve: DifficultyCurve,
+    pub easy: DifficultyLevel,
+    pub hard: DifficultyLevel,
+}
+
+impl Default for DifficultyTuning {
+    fn default() -> Self {
+        Self {
+            curve: DifficultyCurve::default(),
+            easy: DifficultyLevel {
+                speed: -300.0,
+                spawn_interval: 5.0,
+                gap_size: 600.0,
+                vertical_variance: 0.6,
+            },
+            hard: DifficultyLevel {
+                speed: -600.0,
+                spawn_interval: 2.0,
+                gap_size: 450.0,
+                vertical_variance: 1.0,
+            },
+        }
+    }
+}
+
+impl DifficultyTuning {
+    /// Pipe settings after `progress` pipes on `curve`.
+    pub fn level(&self, curve: &DifficultyCurve, progress: f32) -> DifficultyLevel {
+        self.easy.lerp(&self.hard, curve.intensity(progress))
+    }
+}
+
+/// What the game modes other than endless change.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModeTuning {
+    /// How long a timed run lasts, in seconds.
+    pub timed_secs: f32,
+    /// Curve zen runs follow instead of `difficulty.curve`.
+    pub zen_curve: DifficultyCurve,
+    /// Curve hardcore runs follow instead of `difficulty.curve`.
+    pub hardcore_curve: DifficultyCurve,
+}
+
+impl Default for ModeTuning {
+    fn default() -> Self {
+        Self {
+            timed_secs: 60.0,
+            zen_curve: DifficultyCurve::Table(vec![(0.0, 0.0)]),
+            hardcore_curve: DifficultyCurve::Table(vec![(0.0, 1.0)]),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct BoundsTuning {
+    /// How far above the bottom edge of the playfield the ground is.
+    pub floor: f32,
+    /// How far above the top edge of the playfield the ceiling is, negative to bring it lower.
+    pub ceiling: f32,
+    /// Ceiling for runs whose game mode doesn't pick one.
+    pub ceiling_behaviour: Ceiling,
+}
+
+impl Default for BoundsTuning {
+    fn default() -> Self {
+        Self {
+            floor: 0.0,
+            ceiling: 0.0,
+            ceiling_behaviour: Ceiling::Clamp,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CoinTuning {
+    /// Probability that a pipe pair gets a row of coins.
+    pub chance: f64,
+    /// Probability that the row hugs one of the pipes instead of running down the middle.
+    pub risky_chance: f64,
+    pub count: u32,
+    pub spacing: f32,
+}
+
+impl Default for CoinTuning {
+    fn default() -> Self {
+        Self {
+            chance: 0.5,
+            risky_chance: 0.3,
+            count: 3,
+            spacing: 120.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct PowerUpTuning {
+    /// Probability that a pipe pair comes with a pickup.
+    pub chance: f64,
+    pub shield: PowerUpSpawn,
+    pub slow_motion: PowerUpSpawn,
+    pub shrink: PowerUpSpawn,
+    pub score_multiplier: PowerUpSpawn,
+    /// Share of their normal speed pipes and clouds keep in slow motion.
+    pub slow_motion_scale: f32,
+    /// Share of its normal size the bird keeps while shrunk.
+    pub shrink_scale: f32,
+    /// Points per pipe while the score multiplier is active.
+    pub multiplier: u32,
+}
+
+impl Default for PowerUpTuning {
+    fn default() -> Self {
+        Self {
+            chance: 0.15,
+            shield: PowerUpSpawn {
+                weight: 3,
+                duration: 15.0,
+            },
+            slow_motion: PowerUpSpawn {
+                weight: 2,
+                duration: 5.0,
+            },
+            shrink: PowerUpSpawn {
+                weight: 2,
+                duration: 8.0,
+            },
+            score_multiplier: PowerUpSpawn {
+                weight: 1,
+                duration: 10.0,
+            },
+            slow_motion_scale: 0.5,
+            shrink_scale: 0.6,
+            multiplier: 2,
+        }
+    }
+}
+
+impl PowerUpTuning {
+    pub fn spawn(&self, kind: PowerUpKind) -> &PowerUpSpawn {
+        match kind {
+            PowerUpKind::Shield => &self.shield,
+            PowerUpKind::SlowMotion => &self.slow_motion,
+            PowerUpKind::Shrink => &self.shrink,
+            PowerUpKind::ScoreMultiplier => &self.score_multiplier,
+        }
+    }
+}
+
+/// How often a kind of pickup spawns relative to the others, and how long its effect lasts.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PowerUpSpawn {
+    pub weight: u32,
+    pub duration: f32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LivesTuning {
+    /// Crashes a run lasts for. With one, the first crash ends it.
+    pub count: u32,
+    /// How long the bird can't be hurt after respawning.
+    pub invulnerability_secs: f32,
+}
+
+impl Default for LivesTuning {
+    fn default() -> Self {
+        Self {
+            count: 1,
+            invulnerability_secs: 3.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CloudTuning {
+    pub speed_min: f32,
+    pub speed_max: f32,
+    pub scale_min: f32,
+    pub scale_max: f32,
+    pub distance_min: f32,
+    pub distance_max: f32,
+}
+
+impl Default for CloudTuning {
+    fn default() -> Self {
+        Self {
+            speed_min: -500.0,
+            speed_max: -50.0,
+            scale_min: 0.25,
+            scale_max: 2.0,
+            distance_min: 0.0,
+            distance_max: 1.0,
+        }
+    }
+}
+
+impl Tuning {
+    /// The character called `name`, or the first one if there is none.
+    pub fn character(&self, name: &str) -> &CharacterDefinition {
+        self.characters
+            .iter()
+            .find(|character| character.name == name)
+            .unwrap_or(&self.characters[0])
+    }
+
+    /// FNV-1a hash of every value, so a replay can tell whether it is played back with the
+    /// tuning it was recorded with.
+    pub fn fingerprint(&self) -> u64 {
+        format!("{self:?}")
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+
+    /// Parses a tuning file, rejecting it if any of its values would break the game.
+    pub fn from_ron(bytes: &[u8]) -> Result<Self, TuningLoaderError> {
+        let tuning: Self = ron::de::from_bytes(bytes)?;
+        let problems = tuning.problems();
+        if !problems.is_empty() {
+            return Err(TuningLoaderError::Invalid(problems));
+        }
+        Ok(tuning)
+    }
+
+    /// Lists every value that would break the game, rather than stopping at the first.
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut check = |ok: bool, message: &str| {
+            if !ok {
+                problems.push(message.to_string());
+            }
+        };
+
+        check(
+            self.pipes.spawn_first > 0.0,
+            "pipes.spawn_first must be positive",
+        );
+        for spawn in &self.pipes.behaviours {
+            check(
+                (0.0..=1.0).contains(&spawn.chance),
+                "pipes.behaviours chance must be between 0 and 1",
+            );
+            let ok = match spawn.behaviour {
+                PipeBehaviour::Oscillate { amplitude, period } => amplitude >= 0.0 && period > 0.0,
+                PipeBehaviour::OpenClose { closes_by, period } => closes_by >= 0.0 && period > 0.0,
+                PipeBehaviour::ShiftOnApproach {
+                    offset,
+                    distance,
+                    speed,
+                } => offset >= 0.0 && distance > 0.0 && speed > 0.0,
+            };
+            check(
+                ok,
+                "pipes.behaviours need positive periods, distances and speeds and no negative sizes",
+            );
+        }
+
+        for spawn in &self.obstacles {
+            check(
+                (0.0..=1.0).contains(&spawn.chance),
+                "obstacles chance must be between 0 and 1",
+            );
+            if let Some(problem) = spawn.obstacle.problem() {
+                check(false, problem);
+            }
+        }
+
+        let difficulty = &self.difficulty;
+        for (name, curve) in [
+            ("difficulty.curve", &difficulty.curve),
+            ("modes.zen_curve", &self.modes.zen_curve),
+            ("modes.hardcore_curve", &self.modes.hardcore_curve),
+        ] {
+            if let Some(problem) = curve.problem() {
+                check(false, &format!("{name} {problem}"));
+            }
+        }
+        for (name, level) in [("easy", &difficulty.easy), ("hard", &difficulty.hard)] {
+            check(
+                level.speed < 0.0,
+                &format!("difficulty.{name}.speed must be negative, pipes move to the left"),
+            );
+            check(
+                level.spawn_interval > 0.0,
+                &format!("difficulty.{name}.spawn_interval must be positive"),
+            );
+            check(
+                level.gap_size > 0.0,
+                &format!("difficulty.{name}.gap_size must be positive"),
+            );
+            check(
+                (0.0..=1.0).contains(&level.vertical_variance),
+                &format!("difficulty.{name}.vertical_variance must be between 0 and 1"),
+            );
+        }
+
+        check(
+            self.modes.timed_secs > 0.0,
+            "modes.timed_secs must be positive",
+        );
+
+        let coins = &self.coins;
+        check(
+            (0.0..=1.0).contains(&coins.chance),
+            "coins.chance must be between 0 and 1",
+        );
+        check(
+            (0.0..=1.0).contains(&coins.risky_chance),
+            "coins.risky_chance must be between 0 and 1",
+        );
+        check(coins.spacing >= 0.0, "coins.spacing must not be negative");
+
+        let power_ups = &self.power_ups;
+        check(
+            (0.0..=1.0).contains(&power_ups.chance),
+            "power_ups.chance must be between 0 and 1",
+        );
+        check(
+            PowerUpKind::ALL
+                .iter()
+                .all(|kind| power_ups.spawn(*kind).duration > 0.0),
+            "power_ups durations must be positive",
+        );
+        check(
+            PowerUpKind::ALL
+                .iter()
+                .any(|kind| power_ups.spawn(*kind).weight > 0),
+            "power_ups need at least one positive weight",
+        );
+        check(
+            power_ups.slow_motion_scale > 0.0,
+            "power_ups.slow_motion_scale must be positive",
+        );
+        check(
+            power_ups.shrink_scale > 0.0,
+            "power_ups.shrink_scale must be positive",
+        );
+        check(
+            power_ups.multiplier > 0,
+            "power_ups.multiplier must be positive",
+        );
+
+        check(self.lives.count > 0, "lives.count must be positive");
+        check(
+            self.lives.invulnerability_secs >= 0.0,
+            "lives.invulnerability_secs must not be negative",
+        );
+
+        let clouds = &self.clouds;
+        check(
+            clouds.speed_min < clouds.speed_max,
+            "clouds.speed_min must be below clouds.speed_max",
+        );
+        check(clouds.scale_min > 0.0, "clouds.scale_min must be positive");
+        check(
+            clouds.scale_min <= clouds.scale_max,
+            "clouds.scale_min must not be above clouds.scale_max",
+        );
+        check(
+            clouds.distance_min <= clouds.distance_max,
+            "clouds.distance_min must not be above clouds.distance_max",
+        );
+
+        check(
+            !self.characters.is_empty(),
+            "characters must list at least one",
+        );
+        for (index, character) in self.characters.iter().enumerate() {
+            if let Some(problem) = character.problem() {
+                check(false, &problem);
+            }
+            check(
+                self.characters[..index]
+                    .iter()
+                    .all(|other| other.name != character.name),
+                &format!("character {} is listed twice", character.name),
+            );
+        }
+
+        problems
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TuningLoaderError {
+    #[error("could not read tuning file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse tuning file: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("invalid tuning: {}", .0.join("; "))]
+    Invalid(Vec<String>),
+}
+
+#[derive(Default)]
+struct TuningLoader;
+
+impl AssetLoader for TuningLoader {
+    type Asset = Tuning;
+    type Settings = ();
+    type Error = TuningLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Tuning, TuningLoaderError>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            Tuning::from_ron(&bytes)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tuning.ron"]
+    }
+}
+
+/// The tuning file [`Tuning`] follows.
+#[derive(Resource)]
+pub struct TuningHandle(pub Handle<Tuning>);
+
+fn load_tuning(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(TuningHandle(asset_server.load(TUNING_PATH)));
+}
+
+// a file that fails to load or validate never gets here, so the last good values stay
+fn apply_tuning(
+    mut tuning_events: EventReader<AssetEvent<Tuning>>,
+    tuning_assets: Res<Assets<Tuning>>,
+    handle: Res<TuningHandle>,
+    mut tuning: ResMut<Tuning>,
+) {
+    for event in tuning_events.read() {
+        if event.is_loaded_with_dependencies(&handle.0) || event.is_modified(&handle.0) {
+            if let Some(loaded) = tuning_assets.get(&handle.0) {
+                *tuning = loaded.clone();
+                info!("applied tuning from {TUNING_PATH}");
+            }
+        }
+    }
+}
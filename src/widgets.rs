@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+
+const BUTTON_COLOR: Color = Color::rgb(0.086, 0.086, 0.086);
+const BUTTON_HOVERED_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+const BUTTON_TEXT_COLOR: Color = Color::rgb(0.95, 0.95, 0.95);
+
+pub struct WidgetsPlugin;
+
+impl Plugin for WidgetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, highlight_buttons);
+    }
+}
+
+/// Spawns a labelled button tagged with `action`, which menus query for on press.
+pub fn spawn_button(
+    parent: &mut ChildBuilder,
+    font: &Handle<Font>,
+    label: &str,
+    action: impl Component,
+) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                width: Val::Px(150.0),
+                height: Val::Px(50.0),
+                margin: UiRect::all(Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: BUTTON_COLOR.into(),
+            ..default()
+        })
+        .insert(action)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 25.0,
+                    color: BUTTON_TEXT_COLOR,
+                },
+            ));
+        });
+}
+
+fn highlight_buttons(
+    mut q_buttons: Query<(&Interaction, &mut BackgroundColor), Changed<Interaction>>,
+) {
+    for (interaction, mut background_color) in q_buttons.iter_mut() {
+        *background_color = match interaction {
+            Interaction::Hovered | Interaction::Pressed => BUTTON_HOVERED_COLOR.into(),
+            Interaction::None => BUTTON_COLOR.into(),
+        };
+    }
+}